@@ -0,0 +1,304 @@
+//! Helpers for using kiddo with astronomical catalogues, where positions are given as
+//! right ascension and declination in degrees. Positions are stored in a 3D tree as
+//! unit vectors, so that the `squared_euclidean` distance between two entries is their
+//! squared chord length on the unit sphere. The functions in this module take care of
+//! converting angular radii in arcseconds into squared chord lengths, and back again.
+//! As a sanity check against trees built some other way, queries check that the bounds of
+//! the trees they are given lie within the unit sphere, and return
+//! `ErrorKind::MismatchedConfiguration` wrapped in `AstroError::Tree` if not. Only the
+//! bounds are checked, so a tree holding points inside the sphere, such as the origin,
+//! passes and gives wrong separations.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use kiddo::astro;
+//!
+//! let tree = astro::build_tree(vec![(10.0f64, 20.0, "a"), (10.0, 20.001, "b"), (180.0, -45.0, "c")])?;
+//!
+//! let within = astro::within_arcsec(&tree, 10.0, 20.0, 5.0)?;
+//!
+//! assert_eq!(within.len(), 2);
+//! assert_eq!(*within[1].1, "b");
+//! assert!((within[1].0 - 3.6).abs() < 1e-6);
+//! # Ok::<(), kiddo::astro::AstroError>(())
+//! ```
+
+use num_traits::Float;
+
+use crate::distance::squared_euclidean;
+use crate::{ErrorKind, KdTree};
+
+const ARCSEC_PER_RADIAN: f64 = 206_264.806_247_096_36;
+
+/// Converts a right ascension and declination, both in degrees, into a unit vector.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::astro::radec_to_unit_vector;
+///
+/// let v = radec_to_unit_vector(90.0f64, 0.0);
+///
+/// assert!(v[0].abs() < 1e-12);
+/// assert!((v[1] - 1.0).abs() < 1e-12);
+/// assert!(v[2].abs() < 1e-12);
+/// ```
+pub fn radec_to_unit_vector<A: Float>(ra_deg: A, dec_deg: A) -> [A; 3] {
+    let ra = ra_deg.to_radians();
+    let dec = dec_deg.to_radians();
+    [dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin()]
+}
+
+/// Converts an angular separation in arcseconds into the squared chord length between two
+/// points on the unit sphere separated by that angle. Separations of 180 degrees or more
+/// map onto the maximum squared chord length of 4.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::astro::arcsec_to_squared_chord;
+///
+/// assert_eq!(arcsec_to_squared_chord(0.0f64), 0.0);
+/// assert!((arcsec_to_squared_chord(180.0f64 * 3600.0) - 4.0).abs() < 1e-12);
+/// ```
+pub fn arcsec_to_squared_chord<A: Float>(arcsec: A) -> A {
+    let two = A::one() + A::one();
+    let half_angle = (arcsec / A::from(ARCSEC_PER_RADIAN).unwrap() / two)
+        .min(A::from(std::f64::consts::FRAC_PI_2).unwrap());
+    let chord = two * half_angle.sin();
+    chord * chord
+}
+
+/// Converts a squared chord length between two points on the unit sphere back into their
+/// angular separation in arcseconds.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::astro::{arcsec_to_squared_chord, squared_chord_to_arcsec};
+///
+/// let sep = squared_chord_to_arcsec(arcsec_to_squared_chord(1.5f64));
+///
+/// assert!((sep - 1.5).abs() < 1e-9);
+/// ```
+pub fn squared_chord_to_arcsec<A: Float>(squared_chord: A) -> A {
+    let two = A::one() + A::one();
+    let half_chord = (squared_chord.max(A::zero()).sqrt() / two).min(A::one());
+    two * half_chord.asin() * A::from(ARCSEC_PER_RADIAN).unwrap()
+}
+
+/// Builds a 3D tree from an iterator of `(ra_deg, dec_deg, data)` entries.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::astro;
+///
+/// let tree = astro::build_tree(vec![(10.0, 20.0, 1), (11.0, 21.0, 2)])?;
+///
+/// assert_eq!(tree.size(), 2);
+/// # Ok::<(), kiddo::ErrorKind>(())
+/// ```
+pub fn build_tree<A, T, I>(entries: I) -> Result<KdTree<A, T, 3>, ErrorKind>
+where
+    A: Float,
+    T: PartialEq,
+    I: IntoIterator<Item = (A, A, T)>,
+{
+    let mut tree = KdTree::new();
+    for (ra_deg, dec_deg, data) in entries {
+        tree.add(&radec_to_unit_vector(ra_deg, dec_deg), data)?;
+    }
+    Ok(tree)
+}
+
+/// Queries a tree built by `build_tree()` to find all entries within `radius_arcsec` of the
+/// given position. Results are returned sorted nearest-first, as pairs of separation in
+/// arcseconds and data.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::astro;
+///
+/// let tree = astro::build_tree(vec![(10.0, 20.0, 1), (10.0, 21.0, 2)])?;
+///
+/// let within = astro::within_arcsec(&tree, 10.0, 20.0, 60.0)?;
+///
+/// assert_eq!(within, vec![(0.0, &1)]);
+/// # Ok::<(), kiddo::astro::AstroError>(())
+/// ```
+pub fn within_arcsec<A, T>(
+    tree: &KdTree<A, T, 3>,
+    ra_deg: A,
+    dec_deg: A,
+    radius_arcsec: A,
+) -> Result<Vec<(A, &T)>, AstroError>
+where
+    A: Float,
+    T: PartialEq,
+{
+    check_bounds_within_unit_sphere(tree)?;
+    let point = radec_to_unit_vector(ra_deg, dec_deg);
+    let radius = arcsec_to_squared_chord(radius_arcsec);
+
+    Ok(tree
+        .within(&point, radius, &squared_euclidean)?
+        .into_iter()
+        .map(|(dist, data)| (squared_chord_to_arcsec(dist), data))
+        .collect())
+}
+
+/// Queries a tree built by `build_tree()` to find the nearest `num` entries to the given
+/// position. Results are returned sorted nearest-first, as pairs of separation in
+/// arcseconds and data.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::astro;
+///
+/// let tree = astro::build_tree(vec![(10.0f64, 20.0, 1), (10.0, 21.0, 2)])?;
+///
+/// let nearest = astro::nearest_arcsec(&tree, 10.0, 20.9, 1)?;
+///
+/// assert_eq!(*nearest[0].1, 2);
+/// assert!((nearest[0].0 - 360.0).abs() < 1e-6);
+/// # Ok::<(), kiddo::astro::AstroError>(())
+/// ```
+pub fn nearest_arcsec<A, T>(
+    tree: &KdTree<A, T, 3>,
+    ra_deg: A,
+    dec_deg: A,
+    num: usize,
+) -> Result<Vec<(A, &T)>, AstroError>
+where
+    A: Float,
+    T: PartialEq,
+{
+    check_bounds_within_unit_sphere(tree)?;
+    let point = radec_to_unit_vector(ra_deg, dec_deg);
+
+    Ok(tree
+        .nearest(&point, num, &squared_euclidean)?
+        .into_iter()
+        .map(|(dist, data)| (squared_chord_to_arcsec(dist), data))
+        .collect())
+}
+
+/// Cross-matches two catalogues built by `build_tree()`, returning one-to-one best matches
+/// with a separation of at most `radius_arcsec`. Candidate pairs are accepted closest-first,
+/// and each entry of either catalogue appears in at most one match. Results are returned
+/// sorted by separation in arcseconds.
+///
+/// Returns `ErrorKind::MismatchedConfiguration` if the bounds of either tree extend beyond
+/// the unit sphere, as is the case for most trees not built by `build_tree()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::astro;
+///
+/// let gaia = astro::build_tree(vec![(10.0, 20.0, "g1"), (10.0, 20.0005, "g2")])?;
+/// let tmass = astro::build_tree(vec![(10.0, 20.0002, "t1"), (50.0, 50.0, "t2")])?;
+///
+/// let matches = astro::cross_match(&gaia, &tmass, 2.0)?;
+///
+/// assert_eq!(matches.len(), 1);
+/// assert_eq!((matches[0].1, matches[0].2), (&"g1", &"t1"));
+/// # Ok::<(), kiddo::astro::AstroError>(())
+/// ```
+pub fn cross_match<'a, A, T, U>(
+    tree: &'a KdTree<A, T, 3>,
+    other: &'a KdTree<A, U, 3>,
+    radius_arcsec: A,
+) -> Result<Vec<(A, &'a T, &'a U)>, AstroError>
+where
+    A: Float,
+    T: PartialEq,
+    U: PartialEq,
+{
+    check_bounds_within_unit_sphere(tree)?;
+    check_bounds_within_unit_sphere(other)?;
+    let radius = arcsec_to_squared_chord(radius_arcsec);
+
+    let mut candidates: Vec<(A, usize, usize, &T, &U)> = tree
+        .radius_join_positions(other, radius, &squared_euclidean)
+        .into_iter()
+        .zip(other.entries())
+        .enumerate()
+        .flat_map(|(other_idx, (matches, (_, other_item)))| {
+            matches
+                .into_iter()
+                .map(move |(dist, idx, item)| (dist, idx, other_idx, item, other_item))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut other_matched = vec![false; other.size()];
+    let mut matched = vec![false; tree.size()];
+    let mut result = Vec::new();
+
+    for (dist, idx, other_idx, item, other_item) in candidates {
+        if other_matched[other_idx] || matched[idx] {
+            continue;
+        }
+        other_matched[other_idx] = true;
+        matched[idx] = true;
+        result.push((squared_chord_to_arcsec(dist), item, other_item));
+    }
+
+    Ok(result)
+}
+
+/// Checks that the bounds of `tree` lie within the unit sphere. This is cheap enough to run on
+/// every query, but cannot tell whether the points inside those bounds are unit vectors.
+fn check_bounds_within_unit_sphere<A, T>(tree: &KdTree<A, T, 3>) -> Result<(), AstroError>
+where
+    A: Float,
+    T: PartialEq,
+{
+    if tree.size() == 0 {
+        return Ok(());
+    }
+
+    let limit = A::one() + A::from(1e-6).unwrap();
+    let (min_bounds, max_bounds) = tree.bounds();
+    let within_sphere = min_bounds
+        .iter()
+        .chain(max_bounds.iter())
+        .all(|&coord| coord.abs() <= limit);
+
+    if within_sphere {
+        Ok(())
+    } else {
+        Err(AstroError::Tree(ErrorKind::MismatchedConfiguration {
+            reason: "tree bounds extend beyond the unit sphere used by astro::build_tree",
+        }))
+    }
+}
+
+/// An error returned by the queries in this module.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AstroError {
+    /// The underlying `KdTree` rejected the query, or its bounds show that it was not built
+    /// by `build_tree()`.
+    Tree(ErrorKind),
+}
+
+impl From<ErrorKind> for AstroError {
+    fn from(err: ErrorKind) -> Self {
+        AstroError::Tree(err)
+    }
+}
+
+impl std::error::Error for AstroError {}
+
+impl std::fmt::Display for AstroError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            AstroError::Tree(err) => err.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Clustering algorithms built on top of `KdTree` queries.
+
+use std::collections::HashMap;
+
+use num_traits::Float;
+
+use crate::util;
+use crate::KdTree;
+
+/// Clusters the elements of `tree` using DBSCAN, where `eps` is the neighbourhood radius
+/// (in the units returned by `distance`) and `min_pts` is the number of elements, including
+/// the element itself, that must lie within `eps` of an element for it to be a core point.
+///
+/// Returns every element of the tree alongside its cluster label, or `None` if the element
+/// is noise. Clusters are labelled `0, 1, 2, ...` in the order in which they are found.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::KdTree;
+/// use kiddo::cluster;
+/// use kiddo::distance::squared_euclidean;
+///
+/// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+///
+/// tree.add(&[0.0, 0.0], 0)?;
+/// tree.add(&[0.0, 0.1], 1)?;
+/// tree.add(&[0.1, 0.0], 2)?;
+/// tree.add(&[5.0, 5.0], 3)?;
+///
+/// let labels = cluster::dbscan(&tree, 0.05, 3, &squared_euclidean);
+///
+/// assert!(labels.contains(&(&0, Some(0))));
+/// assert!(labels.contains(&(&3, None)));
+/// # Ok::<(), kiddo::ErrorKind>(())
+/// ```
+pub fn dbscan<'a, A, T, F, const K: usize>(
+    tree: &'a KdTree<A, T, K>,
+    eps: A,
+    min_pts: usize,
+    distance: &F,
+) -> Vec<(&'a T, Option<usize>)>
+where
+    A: Float,
+    T: PartialEq,
+    F: Fn(&[A; K], &[A; K]) -> A,
+{
+    let entries: Vec<(&[A; K], &T)> = tree.entries().collect();
+
+    let mut labels: Vec<Option<usize>> = vec![None; entries.len()];
+    let mut visited = vec![false; entries.len()];
+    let mut next_label = 0;
+
+    let mut stack = Vec::new();
+    let mut neighbours = Vec::new();
+    let mut frontier = Vec::new();
+
+    let is_core = |point: &[A; K], stack: &mut Vec<(&'a KdTree<A, T, K>, usize)>| {
+        let mut count = 0;
+        tree.for_each_within(point, eps, distance, stack, |_, _, _| {
+            count += 1;
+            count < min_pts
+        });
+        count >= min_pts
+    };
+
+    for idx in 0..entries.len() {
+        if visited[idx] {
+            continue;
+        }
+        visited[idx] = true;
+
+        if !is_core(entries[idx].0, &mut stack) {
+            continue;
+        }
+
+        let label = next_label;
+        next_label += 1;
+        labels[idx] = Some(label);
+        frontier.push(idx);
+
+        while let Some(curr) = frontier.pop() {
+            neighbours.clear();
+            tree.for_each_within(entries[curr].0, eps, distance, &mut stack, |_, idx, _| {
+                neighbours.push(idx);
+                true
+            });
+
+            for &neighbour in neighbours.iter() {
+                if labels[neighbour].is_none() {
+                    labels[neighbour] = Some(label);
+                }
+                if !visited[neighbour] {
+                    visited[neighbour] = true;
+                    if is_core(entries[neighbour].0, &mut stack) {
+                        frontier.push(neighbour);
+                    }
+                }
+            }
+        }
+    }
+
+    entries
+        .into_iter()
+        .zip(labels)
+        .map(|((_, item), label)| (item, label))
+        .collect()
+}
+
+/// Groups the elements of `tree` using friends-of-friends: any two elements within
+/// `linking_length` of each other (in the units returned by `distance`) are placed in the
+/// same group, as are any elements connected through a chain of such links.
+///
+/// Subtrees whose bounding box is smaller than the linking length are linked as a whole,
+/// without evaluating any distances between their elements.
+///
+/// Returns every element of the tree alongside its group id. Groups are numbered
+/// `0, 1, 2, ...`, and every element belongs to a group, possibly on its own.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::KdTree;
+/// use kiddo::cluster;
+/// use kiddo::distance::squared_euclidean;
+///
+/// let mut tree: KdTree<f64, usize, 1> = KdTree::new();
+///
+/// tree.add(&[0.0], 0)?;
+/// tree.add(&[1.0], 1)?;
+/// tree.add(&[2.0], 2)?;
+/// tree.add(&[5.0], 3)?;
+///
+/// let groups = cluster::friends_of_friends(&tree, 1.0, &squared_euclidean);
+///
+/// assert_eq!(groups, vec![(&0, 0), (&1, 0), (&2, 0), (&3, 1)]);
+/// # Ok::<(), kiddo::ErrorKind>(())
+/// ```
+pub fn friends_of_friends<'a, A, T, F, const K: usize>(
+    tree: &'a KdTree<A, T, K>,
+    linking_length: A,
+    distance: &F,
+) -> Vec<(&'a T, usize)>
+where
+    A: Float,
+    T: PartialEq,
+    F: Fn(&[A; K], &[A; K]) -> A,
+{
+    let mut groups = UnionFind::new(tree.size());
+    if tree.size() > 0 {
+        fof_node(tree, 0, linking_length, distance, &mut groups);
+    }
+
+    let mut ids = HashMap::new();
+    tree.entries()
+        .enumerate()
+        .map(|(idx, (_, item))| {
+            let next_id = ids.len();
+            (item, *ids.entry(groups.find(idx)).or_insert(next_id))
+        })
+        .collect()
+}
+
+fn fof_node<A, T, F, const K: usize>(
+    node: &KdTree<A, T, K>,
+    offset: usize,
+    linking_length: A,
+    distance: &F,
+    groups: &mut UnionFind,
+) where
+    A: Float,
+    T: PartialEq,
+    F: Fn(&[A; K], &[A; K]) -> A,
+{
+    if node.size() == 0 {
+        return;
+    }
+
+    let (min_bounds, max_bounds) = node.bounds();
+    if distance(min_bounds, max_bounds) <= linking_length {
+        groups.union_range(offset, node.size());
+        return;
+    }
+
+    match node.children() {
+        Some((left, right)) => {
+            fof_node(left, offset, linking_length, distance, groups);
+            fof_node(
+                right,
+                offset + left.size(),
+                linking_length,
+                distance,
+                groups,
+            );
+            fof_pair(
+                left,
+                offset,
+                right,
+                offset + left.size(),
+                linking_length,
+                distance,
+                groups,
+            );
+        }
+        None => {
+            let leaf_groups: Vec<_> = node.leaf_groups().unwrap().collect();
+            for (i, (p, p_range)) in leaf_groups.iter().enumerate() {
+                groups.union_range(offset + p_range.start, p_range.len());
+                for (q, q_range) in leaf_groups.iter().skip(i + 1) {
+                    if distance(p, q) <= linking_length {
+                        groups.union(offset + p_range.start, offset + q_range.start);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn fof_pair<A, T, F, const K: usize>(
+    a: &KdTree<A, T, K>,
+    a_offset: usize,
+    b: &KdTree<A, T, K>,
+    b_offset: usize,
+    linking_length: A,
+    distance: &F,
+    groups: &mut UnionFind,
+) where
+    A: Float,
+    T: PartialEq,
+    F: Fn(&[A; K], &[A; K]) -> A,
+{
+    if a.size() == 0 || b.size() == 0 {
+        return;
+    }
+
+    let (a_min, a_max) = a.bounds();
+    let (b_min, b_max) = b.bounds();
+    if util::distance_between_spaces(a_min, a_max, b_min, b_max, distance) > linking_length {
+        return;
+    }
+    if util::distance_between_spaces_max(a_min, a_max, b_min, b_max, distance) <= linking_length {
+        groups.union_range(a_offset, a.size());
+        groups.union_range(b_offset, b.size());
+        groups.union(a_offset, b_offset);
+        return;
+    }
+
+    match (a.children(), b.children()) {
+        (None, None) => {
+            let b_groups: Vec<_> = b.leaf_groups().unwrap().collect();
+            for (p, p_range) in a.leaf_groups().unwrap() {
+                for (q, q_range) in b_groups.iter() {
+                    if distance(p, q) <= linking_length {
+                        groups.union(a_offset + p_range.start, b_offset + q_range.start);
+                    }
+                }
+            }
+        }
+        (Some((left, right)), _) if b.children().is_none() || a.size() >= b.size() => {
+            let right_offset = a_offset + left.size();
+            fof_pair(
+                left,
+                a_offset,
+                b,
+                b_offset,
+                linking_length,
+                distance,
+                groups,
+            );
+            fof_pair(
+                right,
+                right_offset,
+                b,
+                b_offset,
+                linking_length,
+                distance,
+                groups,
+            );
+        }
+        (_, Some((left, right))) => {
+            let right_offset = b_offset + left.size();
+            fof_pair(
+                a,
+                a_offset,
+                left,
+                b_offset,
+                linking_length,
+                distance,
+                groups,
+            );
+            fof_pair(
+                a,
+                a_offset,
+                right,
+                right_offset,
+                linking_length,
+                distance,
+                groups,
+            );
+        }
+        (Some(_), None) => unreachable!(),
+    }
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        UnionFind {
+            parent: (0..size).collect(),
+            rank: vec![0; size],
+        }
+    }
+
+    fn find(&mut self, idx: usize) -> usize {
+        let mut root = idx;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut curr = idx;
+        while self.parent[curr] != root {
+            let next = self.parent[curr];
+            self.parent[curr] = root;
+            curr = next;
+        }
+
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return;
+        }
+
+        if self.rank[a] < self.rank[b] {
+            self.parent[a] = b;
+        } else {
+            self.parent[b] = a;
+            if self.rank[a] == self.rank[b] {
+                self.rank[a] += 1;
+            }
+        }
+    }
+
+    fn union_range(&mut self, start: usize, len: usize) {
+        for idx in start + 1..start + len {
+            self.union(start, idx);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Exports of a `KdTree`'s structure, for visualising how it partitions space when
+//! investigating query performance.
+
+use std::io::{self, Write};
+
+use num_traits::Float;
+
+use crate::KdTree;
+
+/// Writes the stem and leaf hierarchy of `tree` to `writer` as a Graphviz DOT digraph. Stems
+/// are labelled with their split dimension and value, and every node with its size. Edges
+/// to left children are labelled `<`, and to right children `>=`. Returns an error of kind
+/// `InvalidData` if a split value cannot be represented as an `f64`.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::KdTree;
+/// use kiddo::export;
+///
+/// let mut tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2)?;
+///
+/// tree.add(&[0.0, 0.0], 100)?;
+/// tree.add(&[1.0, 0.0], 101)?;
+/// tree.add(&[2.0, 0.0], 102)?;
+///
+/// let mut dot = Vec::new();
+/// export::write_dot(&tree, &mut dot)?;
+/// let dot = String::from_utf8(dot)?;
+///
+/// assert!(dot.contains("n0 [label=\"x0 < 1\\nsize 3\"];"));
+/// assert!(dot.contains("n0 -> n2 [label=\">=\"];"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn write_dot<A, T, W, const K: usize>(tree: &KdTree<A, T, K>, writer: &mut W) -> io::Result<()>
+where
+    A: Float,
+    T: PartialEq,
+    W: Write,
+{
+    writeln!(writer, "digraph kdtree {{")?;
+    writeln!(writer, "    node [shape=box];")?;
+    let mut next_id = 0;
+    write_dot_node(tree, writer, &mut next_id)?;
+    writeln!(writer, "}}")
+}
+
+fn write_dot_node<A, T, W, const K: usize>(
+    node: &KdTree<A, T, K>,
+    writer: &mut W,
+    next_id: &mut usize,
+) -> io::Result<usize>
+where
+    A: Float,
+    T: PartialEq,
+    W: Write,
+{
+    let id = *next_id;
+    *next_id += 1;
+
+    match (node.split_plane(), node.children()) {
+        (Some((dimension, value)), Some((left, right))) => {
+            writeln!(
+                writer,
+                "    n{} [label=\"x{} < {}\\nsize {}\"];",
+                id,
+                dimension,
+                coordinate_to_f64(value)?,
+                node.size()
+            )?;
+            let left_id = write_dot_node(left, writer, next_id)?;
+            writeln!(writer, "    n{} -> n{} [label=\"<\"];", id, left_id)?;
+            let right_id = write_dot_node(right, writer, next_id)?;
+            writeln!(writer, "    n{} -> n{} [label=\">=\"];", id, right_id)?;
+        }
+        _ => {
+            writeln!(
+                writer,
+                "    n{} [label=\"leaf\\nsize {}\", style=rounded];",
+                id,
+                node.size()
+            )?;
+        }
+    }
+
+    Ok(id)
+}
+
+/// Writes an SVG rendering of a 2D `tree` to `writer`, showing the bounding box of every
+/// non-empty node, the split line of every stem across its node's bounding box, and every
+/// point. The `y` axis points up, as in the tree's own coordinate space. Returns an error of
+/// kind `InvalidData` if a coordinate cannot be represented as an `f64`.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::KdTree;
+/// use kiddo::export;
+///
+/// let mut tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2)?;
+///
+/// tree.add(&[0.0, 0.0], 100)?;
+/// tree.add(&[1.0, 1.0], 101)?;
+/// tree.add(&[2.0, 0.5], 102)?;
+///
+/// let mut svg = Vec::new();
+/// export::write_svg(&tree, &mut svg)?;
+/// let svg = String::from_utf8(svg)?;
+///
+/// assert_eq!(svg.matches("<circle").count(), 3);
+/// assert_eq!(svg.matches("<line").count(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn write_svg<A, T, W>(tree: &KdTree<A, T, 2>, writer: &mut W) -> io::Result<()>
+where
+    A: Float,
+    T: PartialEq,
+    W: Write,
+{
+    let (min, max) = if tree.size() > 0 {
+        let (min, max) = tree.bounds();
+        (to_f64(min)?, to_f64(max)?)
+    } else {
+        ([0.0, 0.0], [1.0, 1.0])
+    };
+    let extent = (max[0] - min[0]).max(max[1] - min[1]);
+    let extent = if extent > 0.0 { extent } else { 1.0 };
+    let pad = extent * 0.05;
+    let width = max[0] - min[0] + 2.0 * pad;
+    let height = max[1] - min[1] + 2.0 * pad;
+
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\" height=\"{}\" viewBox=\"{} {} {} {}\">",
+        (800.0 * height / width).round(),
+        min[0] - pad,
+        flip_y(max[1]) - pad,
+        width,
+        height
+    )?;
+    writeln!(writer, "  <g fill=\"none\" stroke-width=\"1\">")?;
+    write_svg_node(tree, writer)?;
+    writeln!(writer, "  </g>")?;
+
+    writeln!(writer, "  <g fill=\"black\">")?;
+    for (point, _) in tree.entries() {
+        let point = to_f64(point)?;
+        writeln!(
+            writer,
+            "    <circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>",
+            point[0],
+            flip_y(point[1]),
+            extent / 200.0
+        )?;
+    }
+    writeln!(writer, "  </g>")?;
+    writeln!(writer, "</svg>")
+}
+
+fn write_svg_node<A, T, W>(node: &KdTree<A, T, 2>, writer: &mut W) -> io::Result<()>
+where
+    A: Float,
+    T: PartialEq,
+    W: Write,
+{
+    if node.size() == 0 {
+        return Ok(());
+    }
+
+    let (min, max) = node.bounds();
+    let (min, max) = (to_f64(min)?, to_f64(max)?);
+    let (stroke, dash) = if node.is_leaf() {
+        ("grey", "")
+    } else {
+        ("lightgrey", " stroke-dasharray=\"4\"")
+    };
+    writeln!(
+        writer,
+        "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" stroke=\"{}\"{} vector-effect=\"non-scaling-stroke\"/>",
+        min[0],
+        flip_y(max[1]),
+        max[0] - min[0],
+        max[1] - min[1],
+        stroke,
+        dash
+    )?;
+
+    if let (Some((dimension, value)), Some((left, right))) = (node.split_plane(), node.children()) {
+        let value = coordinate_to_f64(value)?;
+        let (x1, y1, x2, y2) = if dimension == 0 {
+            (value, flip_y(max[1]), value, flip_y(min[1]))
+        } else {
+            (min[0], flip_y(value), max[0], flip_y(value))
+        };
+        writeln!(
+            writer,
+            "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"red\" vector-effect=\"non-scaling-stroke\"/>",
+            x1, y1, x2, y2
+        )?;
+
+        write_svg_node(left, writer)?;
+        write_svg_node(right, writer)?;
+    }
+
+    Ok(())
+}
+
+/// SVG's `y` axis points down. Subtracting from zero rather than negating avoids writing `-0`.
+fn flip_y(y: f64) -> f64 {
+    0.0 - y
+}
+
+fn to_f64<A: Float>(point: &[A; 2]) -> io::Result<[f64; 2]> {
+    Ok([coordinate_to_f64(point[0])?, coordinate_to_f64(point[1])?])
+}
+
+fn coordinate_to_f64<A: Float>(value: A) -> io::Result<f64> {
+    value.to_f64().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "coordinate cannot be represented as an f64",
+        )
+    })
+}
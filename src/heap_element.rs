@@ -46,3 +46,28 @@ impl<A: Float, T> Into<(A, T)> for HeapElement<A, T> {
         (self.distance, self.element)
     }
 }
+
+pub struct KeyedElement<S, T> {
+    pub key: S,
+    pub element: T,
+}
+
+impl<S: PartialOrd, T> Ord for KeyedElement<S, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.partial_cmp(&other.key).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<S: PartialOrd, T> PartialOrd for KeyedElement<S, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: PartialOrd, T> Eq for KeyedElement<S, T> {}
+
+impl<S: PartialOrd, T> PartialEq for KeyedElement<S, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
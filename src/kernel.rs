@@ -0,0 +1,34 @@
+//! Smoothing kernels for kernel density estimation with `KdTree::density()`.
+
+use num_traits::Float;
+
+/// A smoothing kernel, evaluated on the squared distance between two points scaled by the
+/// bandwidth.
+/// Kernels are not normalised: each has a value of 1 at zero distance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kernel {
+    /// `exp(-u² / 2)`, which has unbounded support.
+    Gaussian,
+    /// `1 - u²` for `u < 1`, and zero beyond.
+    Epanechnikov,
+}
+
+impl Kernel {
+    /// Evaluates the kernel for the given squared scaled distance `u²`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::kernel::Kernel;
+    ///
+    /// assert_eq!(Kernel::Epanechnikov.evaluate(0.25f64), 0.75);
+    /// assert_eq!(Kernel::Epanechnikov.evaluate(2.0f64), 0.0);
+    /// assert_eq!(Kernel::Gaussian.evaluate(0.0f64), 1.0);
+    /// ```
+    pub fn evaluate<A: Float>(&self, squared_u: A) -> A {
+        match self {
+            Kernel::Gaussian => (-squared_u / (A::one() + A::one())).exp(),
+            Kernel::Epanechnikov => (A::one() - squared_u).max(A::zero()),
+        }
+    }
+}
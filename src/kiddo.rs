@@ -1,11 +1,18 @@
+use std::cell::Cell;
 use std::collections::BinaryHeap;
+use std::ops::Range;
 
 use num_traits::{Float, One, Zero};
 
 #[cfg(feature = "serialize")]
 use crate::custom_serde::*;
-use crate::heap_element::HeapElement;
+use crate::heap_element::{HeapElement, KeyedElement};
+use crate::kernel::Kernel;
+use crate::stats::{QueryStats, TreeStats};
 use crate::util;
+use crate::visit::{NodeRef, Visitor};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Deserializer};
 
 trait Stack<T>
 where
@@ -43,6 +50,31 @@ where
     }
 }
 
+/// Records the cost of a query as it runs. The no-op implementation for `()` is used by
+/// queries that don't report statistics, so they pay nothing for it.
+pub(crate) trait QueryRecorder {
+    fn visit_node(&mut self) {}
+    fn scan_leaf(&mut self) {}
+    fn prune_nodes(&mut self, _count: usize) {}
+}
+
+impl QueryRecorder for () {}
+
+impl QueryRecorder for QueryStats {
+    #[inline(always)]
+    fn visit_node(&mut self) {
+        self.nodes_visited += 1;
+    }
+    #[inline(always)]
+    fn scan_leaf(&mut self) {
+        self.leaves_scanned += 1;
+    }
+    #[inline(always)]
+    fn prune_nodes(&mut self, count: usize) {
+        self.nodes_pruned += count;
+    }
+}
+
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub struct KdTree<A, T: std::cmp::PartialEq, const K: usize> {
@@ -52,33 +84,205 @@ pub struct KdTree<A, T: std::cmp::PartialEq, const K: usize> {
     min_bounds: [A; K],
     #[cfg_attr(feature = "serialize", serde(with = "arrays"))]
     max_bounds: [A; K],
+    #[cfg_attr(
+        feature = "serialize",
+        serde(
+            deserialize_with = "deserialize_content",
+            bound(deserialize = "A: Float + Deserialize<'de>, T: PartialEq + Deserialize<'de>")
+        )
+    )]
     content: Node<A, T, K>,
+
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default = "Option::default", skip_serializing_if = "Option::is_none")
+    )]
+    non_finite: Option<Box<NonFinite<A, T, K>>>,
+}
+
+/// The state a tree keeps for its `NonFinitePolicy`. Only the root of a tree has this, and
+/// only once a policy has been set.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serialize",
+    serde(bound(deserialize = "A: Float + Deserialize<'de>, T: PartialEq + Deserialize<'de>"))
+)]
+struct NonFinite<A, T: std::cmp::PartialEq, const K: usize> {
+    policy: NonFinitePolicy<A>,
+    /// A single leaf holding the elements added with non-finite coordinates. Its bounds span
+    /// all finite values, so that queries never prune it.
+    side: KdTree<A, T, K>,
 }
 
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
-pub enum Node<A, T: std::cmp::PartialEq, const K: usize> {
+#[cfg_attr(
+    feature = "serialize",
+    serde(bound(deserialize = "A: Float + Deserialize<'de>, T: PartialEq + Deserialize<'de>"))
+)]
+pub(crate) enum Node<A, T: std::cmp::PartialEq, const K: usize> {
     Stem {
         left: Box<KdTree<A, T, K>>,
         right: Box<KdTree<A, T, K>>,
         split_value: A,
         split_dimension: u8,
     },
+    /// Elements added at identical coordinates share a single entry in `points`. The elements
+    /// stored at `points[i]` are `bucket[ends[i - 1]..ends[i]]`, starting from zero for `i = 0`,
+    /// and `capacity` bounds the number of distinct points.
     Leaf {
         #[cfg_attr(feature = "serialize", serde(with = "vec_arrays"))]
         points: Vec<[A; K]>,
         bucket: Vec<T>,
+        #[cfg_attr(feature = "serialize", serde(default))]
+        ends: Vec<usize>,
         capacity: usize,
     },
 }
 
-#[derive(Debug, PartialEq)]
+/// Deserializes a node, grouping the elements of leaves serialized before elements were
+/// grouped by point. Those have no `ends`, and hold one element for each entry in `points`.
+#[cfg(feature = "serialize")]
+fn deserialize_content<'de, D, A, T, const K: usize>(
+    deserializer: D,
+) -> Result<Node<A, T, K>, D::Error>
+where
+    D: Deserializer<'de>,
+    A: Float + Deserialize<'de>,
+    T: PartialEq + Deserialize<'de>,
+{
+    let mut content = Node::deserialize(deserializer)?;
+    let legacy = match &mut content {
+        Node::Leaf {
+            points,
+            bucket,
+            ends,
+            ..
+        } if ends.is_empty() && !points.is_empty() => {
+            Some((std::mem::take(points), std::mem::take(bucket)))
+        }
+        _ => None,
+    };
+    if let Some((points, bucket)) = legacy {
+        for (point, data) in points.iter().zip(bucket) {
+            KdTree::add_to_leaf(&mut content, point, data);
+        }
+    }
+    Ok(content)
+}
+
+/// How a `KdTree` handles points with NaN or infinite coordinates, both when they are added
+/// to the tree and when they are used as query points.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NonFinitePolicy<A> {
+    /// Non-finite coordinates are rejected with `ErrorKind::NonFiniteCoordinate`. The default.
+    #[default]
+    Reject,
+    /// Infinite coordinates are clamped to `min` or `max`. NaN coordinates are still rejected,
+    /// as they have no bound to be clamped to.
+    Clamp { min: A, max: A },
+    /// Non-finite coordinates of a query point are left out of the distance metric, so that the
+    /// query only considers the remaining dimensions. Points added with non-finite coordinates
+    /// have no position in the tree, so are kept in a side list as per `SideList`. Queries
+    /// from a query point search the side list as well as the tree, leaving each stored
+    /// point's non-finite dimensions out of the distance metric in the same way.
+    ///
+    /// The following do not support this: they still reject query points with non-finite
+    /// coordinates, where they take one, and ignore the side list. They are `reverse_nearest()`,
+    /// `density()`, `density_batch()`, `all_knn()`, `par_all_knn()`, `knn_join()`,
+    /// `radius_join()`, `pair_counts()`, `cluster::dbscan()`, `cluster::friends_of_friends()`
+    /// and `astro::cross_match()`.
+    SkipDimension,
+    /// Points added with non-finite coordinates are kept in a side list, available through
+    /// `non_finite_entries()`, and are never returned by spatial queries. Query points with
+    /// non-finite coordinates are rejected.
+    SideList,
+}
+
+/// The errors that can be returned by `KdTree` operations.
+///
+/// Queries validate their query point before anything else, so a non-finite coordinate is
+/// always reported, even against an empty tree. Queries on an empty tree that return a
+/// collection of results return an empty collection; only queries that must return exactly
+/// one result, such as `nearest_one()`, fail with `Empty`.
+#[derive(Debug, Clone, Copy)]
 pub enum ErrorKind {
-    NonFiniteCoordinate,
+    /// A point had a NaN or infinite coordinate, at index `dimension`.
+    NonFiniteCoordinate { dimension: usize, value: f64 },
+    /// A tree was requested with a per-node capacity of zero.
     ZeroCapacity,
+    /// A tree was requested with a per-node capacity too large to allocate.
+    CapacityOverflow { capacity: usize },
+    /// A tree was used with a metric or helper that expects it to have been built differently.
+    MismatchedConfiguration { reason: &'static str },
+    /// A query that must return a single result was made against an empty tree.
     Empty,
 }
 
+impl PartialEq for ErrorKind {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                ErrorKind::NonFiniteCoordinate { dimension, value },
+                ErrorKind::NonFiniteCoordinate {
+                    dimension: other_dimension,
+                    value: other_value,
+                },
+            ) => {
+                dimension == other_dimension
+                    && (value == other_value || (value.is_nan() && other_value.is_nan()))
+            }
+            (ErrorKind::ZeroCapacity, ErrorKind::ZeroCapacity) => true,
+            (
+                ErrorKind::CapacityOverflow { capacity },
+                ErrorKind::CapacityOverflow {
+                    capacity: other_capacity,
+                },
+            ) => capacity == other_capacity,
+            (
+                ErrorKind::MismatchedConfiguration { reason },
+                ErrorKind::MismatchedConfiguration {
+                    reason: other_reason,
+                },
+            ) => reason == other_reason,
+            (ErrorKind::Empty, ErrorKind::Empty) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The minimum and maximum coordinates of a set of points.
+type PointBounds<A, const K: usize> = ([A; K], [A; K]);
+type NeighboursWithStats<'a, A, T> = (Vec<(A, &'a T)>, QueryStats);
+
+/// A broken invariant found by `KdTree::validate()`. `depth` is the depth of the offending
+/// node, counted in stems from the root, and is 0 for the side list of non-finite points.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// A leaf's items are not split into one non-empty group for each of its points.
+    BucketMismatch {
+        depth: usize,
+        points: usize,
+        items: usize,
+    },
+    /// A node's recorded size differs from the number of elements it holds.
+    SizeMismatch {
+        depth: usize,
+        size: usize,
+        actual: usize,
+    },
+    /// A point lies outside its node's bounds in `dimension`.
+    OutOfBounds { depth: usize, dimension: usize },
+    /// A point lies on the wrong side of a stem's split on `dimension`.
+    WrongSideOfSplit { depth: usize, dimension: usize },
+    /// A stem splits on `dimension`, which is not less than the number of dimensions.
+    InvalidSplitDimension { depth: usize, dimension: usize },
+    /// The side list of non-finite points is not a single leaf.
+    SideListNotLeaf,
+}
+
 impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T, K> {
     /// Creates a new KdTree with default capacity **per node** of 16.
     ///
@@ -100,6 +304,10 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     /// experiment by tuning this value to best suit your workload via benchmarking:
     /// values between 10 and 40 often work best.
     ///
+    /// A node whose points all share identical coordinates cannot be split, so is allowed
+    /// to grow past this capacity. Queries treat such a node as a single point carrying
+    /// many items.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -114,19 +322,104 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
         if capacity == 0 {
             return Err(ErrorKind::ZeroCapacity);
         }
+        // A full leaf must be addressable, although its storage is only allocated as it fills.
+        let entry_size = std::mem::size_of::<[A; K]>()
+            .max(std::mem::size_of::<T>())
+            .max(std::mem::size_of::<usize>());
+        match capacity.checked_mul(entry_size) {
+            Some(bytes) if bytes <= isize::MAX as usize => {}
+            _ => return Err(ErrorKind::CapacityOverflow { capacity }),
+        }
 
         Ok(KdTree {
             size: 0,
             min_bounds: [A::infinity(); K],
             max_bounds: [A::neg_infinity(); K],
             content: Node::Leaf {
-                points: Vec::with_capacity(capacity),
-                bucket: Vec::with_capacity(capacity),
+                points: Vec::new(),
+                bucket: Vec::new(),
+                ends: Vec::new(),
                 capacity,
             },
+            non_finite: None,
         })
     }
 
+    /// Sets how the tree handles points with NaN or infinite coordinates. See
+    /// `NonFinitePolicy` for the available policies; by default they are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::{KdTree, NonFinitePolicy};
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> =
+    ///     KdTree::new().with_non_finite_policy(NonFinitePolicy::Clamp { min: -1e6, max: 1e6 });
+    ///
+    /// tree.add(&[f64::INFINITY, 0.0], 100)?;
+    ///
+    /// let nearest = tree.nearest_one(&[1e6, 0.0], &squared_euclidean)?;
+    ///
+    /// assert_eq!(nearest, (0.0, &100));
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn with_non_finite_policy(mut self, policy: NonFinitePolicy<A>) -> Self {
+        match &mut self.non_finite {
+            Some(non_finite) => non_finite.policy = policy,
+            None => {
+                self.non_finite = Some(Box::new(NonFinite {
+                    policy,
+                    side: KdTree {
+                        size: 0,
+                        min_bounds: [A::min_value(); K],
+                        max_bounds: [A::max_value(); K],
+                        content: Node::Leaf {
+                            points: Vec::new(),
+                            bucket: Vec::new(),
+                            ends: Vec::new(),
+                            capacity: usize::MAX,
+                        },
+                        non_finite: None,
+                    },
+                }))
+            }
+        }
+        self
+    }
+
+    /// Returns the policy the tree uses for points with NaN or infinite coordinates.
+    pub fn non_finite_policy(&self) -> NonFinitePolicy<A> {
+        self.non_finite
+            .as_ref()
+            .map_or(NonFinitePolicy::Reject, |non_finite| non_finite.policy)
+    }
+
+    /// Returns an iterator over the elements that were added with non-finite coordinates and
+    /// kept in the side list, under the `SideList` and `SkipDimension` policies. These
+    /// elements are not counted by `size()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::{KdTree, NonFinitePolicy};
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> =
+    ///     KdTree::new().with_non_finite_policy(NonFinitePolicy::SideList);
+    ///
+    /// tree.add(&[1.0, 2.0], 100)?;
+    /// tree.add(&[f64::NAN, 2.0], 101)?;
+    ///
+    /// assert_eq!(tree.size(), 1);
+    /// assert_eq!(tree.non_finite_entries().map(|(_, item)| *item).collect::<Vec<_>>(), vec![101]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn non_finite_entries(&self) -> impl Iterator<Item = (&[A; K], &T)> {
+        self.non_finite
+            .iter()
+            .flat_map(|non_finite| non_finite.side.entries())
+    }
+
     /// Creates a new KdTree with a specific capacity **per node**.
     ///
     #[deprecated(since = "0.1.8", note = "with_capacity has a misleading name. Users should instead use with_per_node_capacity. with_capacity will be removed in a future release")]
@@ -134,7 +427,8 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
         Self::with_per_node_capacity(capacity)
     }
 
-    /// Returns the current number of elements stored in the tree
+    /// Returns the current number of elements stored in the tree, excluding any kept in the
+    /// side list of elements with non-finite coordinates
     ///
     /// # Examples
     ///
@@ -153,6 +447,318 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
         self.size
     }
 
+    /// Walks the tree to summarise its shape: leaf depths, node counts, how full the leaves
+    /// are, how often each dimension is split on, and an estimate of its heap memory usage.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2)?;
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    /// tree.add(&[2.0, 0.0], 102)?;
+    ///
+    /// let stats = tree.stats();
+    ///
+    /// assert_eq!((stats.stems, stats.leaves), (1, 2));
+    /// assert_eq!(stats.leaf_fill, vec![0, 1, 1]);
+    /// assert_eq!(stats.split_dimensions, vec![1, 0]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats {
+            size: self.size,
+            min_depth: usize::MAX,
+            max_depth: 0,
+            mean_depth: 0.0,
+            stems: 0,
+            leaves: 0,
+            leaf_fill: Vec::new(),
+            over_capacity_leaves: 0,
+            split_dimensions: vec![0; K],
+            memory_bytes: 0,
+        };
+        if let Some(non_finite) = &self.non_finite {
+            if let Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } = &non_finite.side.content
+            {
+                stats.memory_bytes += std::mem::size_of::<NonFinite<A, T, K>>()
+                    + points.capacity() * std::mem::size_of::<[A; K]>()
+                    + bucket.capacity() * std::mem::size_of::<T>()
+                    + ends.capacity() * std::mem::size_of::<usize>();
+            }
+        }
+        let mut total_depth = 0;
+
+        let mut stack = vec![(self, 0)];
+        while let Some((curr, depth)) = stack.pop() {
+            match &curr.content {
+                Node::Stem {
+                    left,
+                    right,
+                    split_dimension,
+                    ..
+                } => {
+                    stats.stems += 1;
+                    stats.split_dimensions[*split_dimension as usize] += 1;
+                    stats.memory_bytes += 2 * std::mem::size_of::<Self>();
+                    stack.push((right, depth + 1));
+                    stack.push((left, depth + 1));
+                }
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    capacity,
+                } => {
+                    stats.leaves += 1;
+                    stats.min_depth = stats.min_depth.min(depth);
+                    stats.max_depth = stats.max_depth.max(depth);
+                    total_depth += depth;
+
+                    if points.len() > *capacity {
+                        stats.over_capacity_leaves += 1;
+                    } else {
+                        if stats.leaf_fill.len() <= points.len() {
+                            stats.leaf_fill.resize(points.len() + 1, 0);
+                        }
+                        stats.leaf_fill[points.len()] += 1;
+                    }
+
+                    stats.memory_bytes += points.capacity() * std::mem::size_of::<[A; K]>()
+                        + bucket.capacity() * std::mem::size_of::<T>()
+                        + ends.capacity() * std::mem::size_of::<usize>();
+                }
+            }
+        }
+
+        stats.mean_depth = total_depth as f64 / stats.leaves as f64;
+        stats
+    }
+
+    /// Checks that the tree is internally consistent: that every node's size matches its
+    /// contents, every leaf groups its items by point, every stem splits on one of the
+    /// tree's dimensions, every point lies within the bounds of each node containing it,
+    /// and every point is on the correct side of each split above it. The side list of
+    /// non-finite points, if any, must be a single leaf whose size matches its contents and
+    /// which groups its items by point. Useful after deserializing a tree, or after many
+    /// removals.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2)?;
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    /// tree.add(&[2.0, 0.0], 102)?;
+    /// tree.remove(&[1.0, 0.0], &101)?;
+    ///
+    /// assert_eq!(tree.validate(), Ok(()));
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_node(0)?;
+        match &self.non_finite {
+            Some(non_finite) => non_finite.side.validate_side_list(),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates the side list of non-finite points, whose points are not checked against
+    /// its bounds as they may hold NaN coordinates.
+    fn validate_side_list(&self) -> Result<(), ValidationError> {
+        match &self.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => {
+                validate_groups(points, bucket, ends, 0)?;
+                if self.size != bucket.len() {
+                    return Err(ValidationError::SizeMismatch {
+                        depth: 0,
+                        size: self.size,
+                        actual: bucket.len(),
+                    });
+                }
+                Ok(())
+            }
+            Node::Stem { .. } => Err(ValidationError::SideListNotLeaf),
+        }
+    }
+
+    /// Validates this node and everything below it, returning the tight bounds of the
+    /// points it holds, or `None` if it holds none.
+    fn validate_node(&self, depth: usize) -> Result<Option<PointBounds<A, K>>, ValidationError> {
+        let (actual, bounds) = match &self.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => {
+                validate_groups(points, bucket, ends, depth)?;
+
+                let bounds = points.iter().fold(None, |bounds, p| match bounds {
+                    None => Some((*p, *p)),
+                    Some((mut min, mut max)) => {
+                        for ((l, h), v) in min.iter_mut().zip(max.iter_mut()).zip(p.iter()) {
+                            *l = l.min(*v);
+                            *h = h.max(*v);
+                        }
+                        Some((min, max))
+                    }
+                });
+                for p in points {
+                    self.validate_within_bounds(p, p, depth)?;
+                }
+                (bucket.len(), bounds)
+            }
+            Node::Stem {
+                left,
+                right,
+                split_value,
+                split_dimension,
+            } => {
+                let dimension = *split_dimension as usize;
+                if dimension >= K {
+                    return Err(ValidationError::InvalidSplitDimension { depth, dimension });
+                }
+
+                let left_bounds = left.validate_node(depth + 1)?;
+                let right_bounds = right.validate_node(depth + 1)?;
+
+                if let Some((_, max)) = &left_bounds {
+                    if max[dimension] >= *split_value {
+                        return Err(ValidationError::WrongSideOfSplit { depth, dimension });
+                    }
+                }
+                if let Some((min, _)) = &right_bounds {
+                    if min[dimension] < *split_value {
+                        return Err(ValidationError::WrongSideOfSplit { depth, dimension });
+                    }
+                }
+
+                let bounds = match (left_bounds, right_bounds) {
+                    (None, bounds) | (bounds, None) => bounds,
+                    (Some((mut min, mut max)), Some((right_min, right_max))) => {
+                        for (l, r) in min.iter_mut().zip(right_min.iter()) {
+                            *l = l.min(*r);
+                        }
+                        for (h, r) in max.iter_mut().zip(right_max.iter()) {
+                            *h = h.max(*r);
+                        }
+                        Some((min, max))
+                    }
+                };
+                if let Some((min, max)) = &bounds {
+                    self.validate_within_bounds(min, max, depth)?;
+                }
+                (left.size + right.size, bounds)
+            }
+        };
+
+        if self.size != actual {
+            return Err(ValidationError::SizeMismatch {
+                depth,
+                size: self.size,
+                actual,
+            });
+        }
+
+        Ok(bounds)
+    }
+
+    fn validate_within_bounds(
+        &self,
+        min: &[A; K],
+        max: &[A; K],
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        let dimension = (0..K)
+            .find(|&dim| !(min[dim] >= self.min_bounds[dim] && max[dim] <= self.max_bounds[dim]));
+        match dimension {
+            Some(dimension) => Err(ValidationError::OutOfBounds { depth, dimension }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns a read-only cursor positioned at the root node of the tree, from which
+    /// custom traversals can be written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2)?;
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    /// tree.add(&[2.0, 0.0], 102)?;
+    ///
+    /// let root = tree.root();
+    ///
+    /// assert_eq!(root.split(), Some((0, 1.0)));
+    /// assert_eq!(root.left().unwrap().size(), 1);
+    /// assert_eq!(root.right().unwrap().bounds(), (&[1.0, 0.0], &[2.0, 0.0]));
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn root(&self) -> NodeRef<'_, A, T, K> {
+        NodeRef::new(self)
+    }
+
+    /// Walks the tree depth-first, left child before right, calling `visitor.enter()` as each
+    /// node is reached and `visitor.leave()` once it and its children are done with. If
+    /// `enter()` returns `false`, the node's children are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::visit::{NodeRef, Visitor};
+    ///
+    /// struct LeafSizes(Vec<usize>);
+    ///
+    /// impl<'a> Visitor<'a, f64, usize, 2> for LeafSizes {
+    ///     fn enter(&mut self, node: NodeRef<'a, f64, usize, 2>) -> bool {
+    ///         if node.is_leaf() {
+    ///             self.0.push(node.size());
+    ///         }
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2)?;
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    /// tree.add(&[2.0, 0.0], 102)?;
+    ///
+    /// let mut sizes = LeafSizes(Vec::new());
+    /// tree.visit(&mut sizes);
+    ///
+    /// assert_eq!(sizes.0, vec![1, 2]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn visit<'a, V>(&'a self, visitor: &mut V)
+    where
+        V: Visitor<'a, A, T, K>,
+    {
+        self.root().accept(visitor);
+    }
+
     /// Returns true if the node is a leaf node
     ///
     /// # Examples
@@ -212,46 +818,11 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     where
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        self.check_point(point)?;
-
-        let num = std::cmp::min(num, self.size);
-        if num == 0 {
-            return Ok(vec![]);
-        }
-
-        let mut pending = BinaryHeap::new();
-        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
-
-        pending.push(HeapElement {
-            distance: A::zero(),
-            element: self,
-        });
-
-        while !pending.is_empty()
-            && (evaluated.len() < num
-                || (-pending.peek().unwrap().distance <= evaluated.peek().unwrap().distance))
-        {
-            self.nearest_step(
-                point,
-                num,
-                A::infinity(),
-                distance,
-                &mut pending,
-                &mut evaluated,
-            );
-        }
-
-        Ok(evaluated
-            .into_sorted_vec()
-            .into_iter()
-            .take(num)
-            .map(Into::into)
-            .collect())
+        self.nearest_impl(point, num, distance, &mut ())
     }
 
-    /// Queries the tree to find the nearest element to `point`, using the specified
-    /// distance metric function. Faster than querying for nearest(point, 1, ...) due
-    /// to not needing to allocate a Vec for the result
+    /// Queries the tree to find the nearest `num` elements to `point`, as per `nearest()`,
+    /// additionally returning statistics on the cost of the query.
     ///
     /// # Examples
     ///
@@ -264,81 +835,146 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     /// tree.add(&[1.0, 2.0, 5.0], 100)?;
     /// tree.add(&[2.0, 3.0, 6.0], 101)?;
     ///
-    /// let nearest = tree.nearest_one(&[1.0, 2.0, 5.1], &squared_euclidean)?;
+    /// let (nearest, stats) = tree.nearest_with_stats(&[1.0, 2.0, 5.1], 1, &squared_euclidean)?;
     ///
-    /// assert!((nearest.0 - 0.01f64).abs() < f64::EPSILON);
-    /// assert_eq!(*nearest.1, 100);
+    /// assert_eq!(*nearest[0].1, 100);
+    /// assert_eq!(stats.leaves_scanned, 1);
+    /// assert_eq!(stats.distance_evaluations, 2);
     /// # Ok::<(), kiddo::ErrorKind>(())
     /// ```
-    // TODO: pending only ever gets to about 7 items max. try doing this
-    //       recursively to avoid the alloc/dealloc of the vec
-    pub fn nearest_one<F>(&self, point: &[A; K], distance: &F) -> Result<(A, &T), ErrorKind>
+    pub fn nearest_with_stats<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+    ) -> Result<NeighboursWithStats<'_, A, T>, ErrorKind>
     where
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        if self.size == 0 {
-            return Err(ErrorKind::Empty);
-        }
-        self.check_point(point)?;
-
-        let mut pending = Vec::with_capacity(16);
+        let mut stats = QueryStats::default();
+        let evaluations = Cell::new(0);
+        let distance = |a: &[A; K], b: &[A; K]| {
+            evaluations.set(evaluations.get() + 1);
+            distance(a, b)
+        };
 
-        let mut best_dist: A = A::infinity();
-        let mut best_elem: Option<&T> = None;
+        let nearest = self.nearest_impl(point, num, &distance, &mut stats)?;
+        stats.distance_evaluations = evaluations.get();
+        Ok((nearest, stats))
+    }
 
-        pending.push(HeapElement {
-            distance: A::zero(),
-            element: self,
-        });
+    fn nearest_impl<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+        recorder: &mut impl QueryRecorder,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut pending = BinaryHeap::new();
+        let mut evaluated = BinaryHeap::new();
+        let num =
+            self.nearest_into(point, num, distance, &mut pending, &mut evaluated, recorder)?;
 
-        while !pending.is_empty() && (best_elem.is_none() || (pending[0].distance < best_dist)) {
-            self.nearest_one_step(
-                point,
-                distance,
-                &mut pending,
-                &mut best_dist,
-                &mut best_elem,
-            );
-        }
+        Ok(evaluated
+            .into_sorted_vec()
+            .into_iter()
+            .take(num)
+            .map(Into::into)
+            .collect())
+    }
 
-        Ok((best_dist, best_elem.unwrap()))
+    /// Runs a `nearest()` query using the supplied buffers, which are cleared first, leaving
+    /// the results unsorted in `evaluated`. Returns `num` clamped to the size of the tree.
+    pub(crate) fn nearest_into<'a, F>(
+        &'a self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+        pending: &mut BinaryHeap<HeapElement<A, &'a Self>>,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'a T>>,
+        recorder: &mut impl QueryRecorder,
+    ) -> Result<usize, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        Ok(match skip {
+            None => {
+                self.nearest_into_unchecked(&point, num, distance, pending, evaluated, recorder)
+            }
+            Some(skip) => self.nearest_into_unchecked(
+                &point,
+                num,
+                &util::skip_dimensions(distance, skip),
+                pending,
+                evaluated,
+                recorder,
+            ),
+        })
     }
 
-    fn within_impl<F>(
-        &self,
+    fn nearest_into_unchecked<'a, F>(
+        &'a self,
         point: &[A; K],
-        radius: A,
+        num: usize,
         distance: &F,
-    ) -> Result<BinaryHeap<HeapElement<A, &T>>, ErrorKind>
+        pending: &mut BinaryHeap<HeapElement<A, &'a Self>>,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'a T>>,
+        recorder: &mut impl QueryRecorder,
+    ) -> usize
     where
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        self.check_point(point)?;
+        pending.clear();
+        evaluated.clear();
 
-        let mut pending = BinaryHeap::new();
-        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
+        let num = std::cmp::min(num, self.searchable_size());
+        if num == 0 {
+            return 0;
+        }
 
-        pending.push(HeapElement {
-            distance: A::zero(),
-            element: self,
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::zero(), pending, |pending| {
+            self.nearest_step(
+                point,
+                num,
+                A::infinity(),
+                &side_distance,
+                &|_| true,
+                pending,
+                evaluated,
+                recorder,
+            )
         });
 
-        while !pending.is_empty() && (-pending.peek().unwrap().distance <= radius) {
+        while !pending.is_empty()
+            && (evaluated.len() < num
+                || (-pending.peek().unwrap().distance <= evaluated.peek().unwrap().distance))
+        {
             self.nearest_step(
                 point,
-                self.size,
-                radius,
+                num,
+                A::infinity(),
                 distance,
-                &mut pending,
-                &mut evaluated,
+                &|_| true,
+                pending,
+                evaluated,
+                recorder,
             );
         }
 
-        Ok(evaluated)
+        recorder.prune_nodes(pending.len());
+
+        num
     }
 
-    /// Queries the tree to find all elements within `radius` of `point`, using the specified
-    /// distance metric function. Results are returned sorted nearest-first
+    /// Queries the tree to find the nearest `num` elements to `point` that are no further than
+    /// `max_radius` from it, using the specified distance metric function. Results are
+    /// returned sorted nearest-first. Faster than filtering the results of `iter_nearest()`,
+    /// as nodes beyond `max_radius` are pruned from the start of the query.
     ///
     /// # Examples
     ///
@@ -352,35 +988,96 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     /// tree.add(&[2.0, 3.0, 6.0], 101)?;
     /// tree.add(&[200.0, 300.0, 600.0], 102)?;
     ///
-    /// let within = tree.within(&[1.0, 2.0, 5.0], 10f64, &squared_euclidean)?;
+    /// let nearest = tree.nearest_within(&[1.0, 2.0, 5.0], 3, 10f64, &squared_euclidean)?;
     ///
-    /// assert_eq!(within.len(), 2);
+    /// assert_eq!(nearest, vec![(0.0, &100), (3.0, &101)]);
     /// # Ok::<(), kiddo::ErrorKind>(())
     /// ```
-    pub fn within<F>(
+    pub fn nearest_within<F>(
         &self,
         point: &[A; K],
-        radius: A,
+        num: usize,
+        max_radius: A,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.nearest_within_unchecked(&point, num, max_radius, distance),
+            Some(skip) => self.nearest_within_unchecked(
+                &point,
+                num,
+                max_radius,
+                &util::skip_dimensions(distance, skip),
+            ),
+        }
+    }
+
+    fn nearest_within_unchecked<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        max_radius: A,
         distance: &F,
     ) -> Result<Vec<(A, &T)>, ErrorKind>
     where
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        if self.size == 0 {
+        let num = std::cmp::min(num, self.searchable_size());
+        if num == 0 {
             return Ok(vec![]);
         }
 
-        self.within_impl(point, radius, distance).map(|evaluated| {
-            evaluated
-                .into_sorted_vec()
-                .into_iter()
-                .map(Into::into)
-                .collect()
-        })
+        let mut pending = BinaryHeap::new();
+        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
+
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::zero(), &mut pending, |pending| {
+            self.nearest_step(
+                point,
+                num,
+                max_radius,
+                &side_distance,
+                &|_| true,
+                pending,
+                &mut evaluated,
+                &mut (),
+            )
+        });
+
+        while !pending.is_empty()
+            && (-pending.peek().unwrap().distance <= max_radius)
+            && (evaluated.len() < num
+                || (-pending.peek().unwrap().distance <= evaluated.peek().unwrap().distance))
+        {
+            self.nearest_step(
+                point,
+                num,
+                max_radius,
+                distance,
+                &|_| true,
+                &mut pending,
+                &mut evaluated,
+                &mut (),
+            );
+        }
+
+        Ok(evaluated
+            .into_sorted_vec()
+            .into_iter()
+            .take(num)
+            .map(Into::into)
+            .collect())
     }
 
-    /// Queries the tree to find all elements within `radius` of `point`, using the specified
-    /// distance metric function. Results are returned in arbitrary order. Faster than within()
+    /// Queries the tree to find `num` elements that are approximately the nearest to `point`,
+    /// using the specified distance metric function. Nodes are pruned once their distance
+    /// from `point` exceeds `current_kth / (1 + epsilon)`, so every returned distance is
+    /// within a factor of `1 + epsilon` of the true `k`th nearest distance. `epsilon` is
+    /// applied to the values returned by `distance`, so with `squared_euclidean` it bounds
+    /// the squared distance. An `epsilon` of zero gives the same results as `nearest()`.
     ///
     /// # Examples
     ///
@@ -392,33 +1089,30 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     ///
     /// tree.add(&[1.0, 2.0, 5.0], 100)?;
     /// tree.add(&[2.0, 3.0, 6.0], 101)?;
-    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
     ///
-    /// let within = tree.within(&[1.0, 2.0, 5.0], 10f64, &squared_euclidean)?;
+    /// let nearest = tree.nearest_approx(&[1.0, 2.0, 5.1], 1, 0.5, &squared_euclidean)?;
     ///
-    /// assert_eq!(within.len(), 2);
+    /// assert_eq!(nearest.len(), 1);
+    /// assert_eq!(*nearest[0].1, 100);
     /// # Ok::<(), kiddo::ErrorKind>(())
     /// ```
-    pub fn within_unsorted<F>(
+    pub fn nearest_approx<F>(
         &self,
         point: &[A; K],
-        radius: A,
+        num: usize,
+        epsilon: A,
         distance: &F,
     ) -> Result<Vec<(A, &T)>, ErrorKind>
     where
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        if self.size == 0 {
-            return Ok(vec![]);
-        }
-
-        self.within_impl(point, radius, distance)
-            .map(|evaluated| evaluated.into_vec().into_iter().map(Into::into).collect())
+        self.nearest_approx_impl(point, num, epsilon, usize::MAX, distance)
     }
 
-    /// Queries the tree to find the best `n` elements within `radius` of `point`, using the specified
-    /// distance metric function. Results are returned in arbitrary order. 'Best' is determined by
-    /// performing a comparison of the elements using < (ie, std::ord::lt)
+    /// Queries the tree to find `num` elements that are approximately the nearest to `point`,
+    /// as per `nearest_approx()`, but additionally stops searching once `max_leaves` leaf
+    /// nodes have been scanned. This puts a hard upper bound on the cost of the query, at
+    /// the expense of any guarantee on the quality of the results.
     ///
     /// # Examples
     ///
@@ -426,60 +1120,124 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     /// use kiddo::KdTree;
     /// use kiddo::distance::squared_euclidean;
     ///
-    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::with_per_node_capacity(1)?;
     ///
     /// tree.add(&[1.0, 2.0, 5.0], 100)?;
-    /// tree.add(&[2.0, 3.0, 6.0], 1)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
     /// tree.add(&[200.0, 300.0, 600.0], 102)?;
     ///
-    /// let best_n_within = tree.best_n_within(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean)?;
+    /// let nearest = tree.nearest_approx_max_leaves(&[1.0, 2.0, 5.1], 3, 0.0, 1, &squared_euclidean)?;
     ///
-    /// assert_eq!(best_n_within[0], 1);
+    /// assert_eq!(nearest.len(), 1);
+    /// assert_eq!(*nearest[0].1, 100);
     /// # Ok::<(), kiddo::ErrorKind>(())
     /// ```
-    pub fn best_n_within<F>(
+    pub fn nearest_approx_max_leaves<F>(
         &self,
         point: &[A; K],
-        radius: A,
-        max_qty: usize,
+        num: usize,
+        epsilon: A,
+        max_leaves: usize,
         distance: &F,
-    ) -> Result<Vec<T>, ErrorKind>
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        self.nearest_approx_impl(point, num, epsilon, max_leaves, distance)
+    }
+
+    fn nearest_approx_impl<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        epsilon: A,
+        max_leaves: usize,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.nearest_approx_unchecked(&point, num, epsilon, max_leaves, distance),
+            Some(skip) => self.nearest_approx_unchecked(
+                &point,
+                num,
+                epsilon,
+                max_leaves,
+                &util::skip_dimensions(distance, skip),
+            ),
+        }
+    }
+
+    fn nearest_approx_unchecked<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        epsilon: A,
+        max_leaves: usize,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
     where
         F: Fn(&[A; K], &[A; K]) -> A,
-        T: Copy + Ord,
     {
-        if self.size == 0 {
+        let num = std::cmp::min(num, self.searchable_size());
+        if num == 0 {
             return Ok(vec![]);
         }
 
-        self.check_point(point)?;
+        let slack = A::one() + epsilon.max(A::zero());
+        let mut leaves_visited = 0;
 
-        let mut pending = Vec::with_capacity(max_qty);
-        let mut evaluated = BinaryHeap::<T>::new();
+        let mut pending = BinaryHeap::new();
+        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
 
-        pending.push(HeapElement {
-            distance: A::zero(),
-            element: self,
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::zero(), &mut pending, |pending| {
+            self.nearest_step(
+                point,
+                num,
+                A::infinity(),
+                &side_distance,
+                &|_| true,
+                pending,
+                &mut evaluated,
+                &mut (),
+            );
+            leaves_visited += 1;
         });
 
-        while !pending.is_empty() {
-            self.best_n_within_step(
+        while !pending.is_empty()
+            && leaves_visited < max_leaves
+            && (evaluated.len() < num
+                || (-pending.peek().unwrap().distance * slack
+                    <= evaluated.peek().unwrap().distance))
+        {
+            self.nearest_step(
                 point,
-                self.size,
-                max_qty,
-                radius,
+                num,
+                A::infinity(),
                 distance,
+                &|_| true,
                 &mut pending,
                 &mut evaluated,
+                &mut (),
             );
+            leaves_visited += 1;
         }
 
-        Ok(evaluated.into_vec().into_iter().collect())
+        Ok(evaluated
+            .into_sorted_vec()
+            .into_iter()
+            .take(num)
+            .map(Into::into)
+            .collect())
     }
 
-    /// Queries the tree to find the best `n` elements within `radius` of `point`, using the specified
-    /// distance metric function. Results are returned in arbitrary order. 'Best' is determined by
-    /// performing a comparison of the elements using < (ie, std::ord::lt). Returns an iterator.
+    /// Queries the tree to find the nearest `num` elements to `point`, using the specified
+    /// distance metric function, skipping any elements that are equal to `exclude`. This is
+    /// useful when querying with the location of an element that is already stored in the
+    /// tree, which would otherwise be returned as its own nearest neighbour.
     ///
     /// # Examples
     ///
@@ -490,208 +1248,2346 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
     ///
     /// tree.add(&[1.0, 2.0, 5.0], 100)?;
-    /// tree.add(&[2.0, 3.0, 6.0], 1)?;
-    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    /// tree.add(&[1.0, 2.0, 5.0], 101)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 102)?;
     ///
-    /// let mut best_n_within_iter = tree.best_n_within_into_iter(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean);
-    /// let first = best_n_within_iter.next().unwrap();
+    /// let nearest = tree.nearest_excluding(&[1.0, 2.0, 5.0], 2, &squared_euclidean, &100)?;
     ///
-    /// assert_eq!(first, 1);
+    /// assert_eq!(nearest, vec![(0.0, &101), (3.0, &102)]);
     /// # Ok::<(), kiddo::ErrorKind>(())
     /// ```
-    pub fn best_n_within_into_iter<F>(
+    pub fn nearest_excluding<F>(
         &self,
         point: &[A; K],
-        radius: A,
-        max_qty: usize,
+        num: usize,
         distance: &F,
-    ) -> impl Iterator<Item = T>
+        exclude: &T,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
     where
         F: Fn(&[A; K], &[A; K]) -> A,
-        T: Copy + Ord,
     {
-        // if let Err(err) = self.check_point(point) {
-        //     return Err(err);
-        // }
-        // if self.size == 0 {
-        //     return std::iter::empty::<T>();
-        // }
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.nearest_excluding_unchecked(&point, num, distance, exclude),
+            Some(skip) => self.nearest_excluding_unchecked(
+                &point,
+                num,
+                &util::skip_dimensions(distance, skip),
+                exclude,
+            ),
+        }
+    }
 
-        let mut pending = Vec::with_capacity(max_qty);
-        let mut evaluated = BinaryHeap::<T>::new();
+    fn nearest_excluding_unchecked<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+        exclude: &T,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let num = std::cmp::min(num, self.searchable_size());
+        if num == 0 {
+            return Ok(vec![]);
+        }
 
-        pending.push(HeapElement {
-            distance: A::zero(),
-            element: self,
+        let mut pending = BinaryHeap::new();
+        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
+
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::zero(), &mut pending, |pending| {
+            self.nearest_step(
+                point,
+                num,
+                A::infinity(),
+                &side_distance,
+                &|d: &T| d != exclude,
+                pending,
+                &mut evaluated,
+                &mut (),
+            )
         });
 
-        while !pending.is_empty() {
-            self.best_n_within_step(
+        while !pending.is_empty()
+            && (evaluated.len() < num
+                || (-pending.peek().unwrap().distance <= evaluated.peek().unwrap().distance))
+        {
+            self.nearest_step(
                 point,
-                self.size,
-                max_qty,
-                radius,
+                num,
+                A::infinity(),
                 distance,
+                &|d: &T| d != exclude,
                 &mut pending,
                 &mut evaluated,
+                &mut (),
             );
         }
 
-        evaluated.into_iter()
+        Ok(evaluated
+            .into_sorted_vec()
+            .into_iter()
+            .take(num)
+            .map(Into::into)
+            .collect())
     }
 
-    fn best_n_within_step<'b, F>(
-        &self,
-        point: &[A; K],
-        _num: usize,
-        max_qty: usize,
-        max_dist: A,
-        distance: &F,
-        pending: &mut Vec<HeapElement<A, &'b Self>>,
-        evaluated: &mut BinaryHeap<T>,
-    ) where
-        F: Fn(&[A; K], &[A; K]) -> A,
-        T: Copy + Ord,
+    /// Queries the tree to find the nearest element to `point`, using the specified
+    /// distance metric function. Faster than querying for nearest(point, 1, ...) due
+    /// to not needing to allocate a Vec for the result
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    ///
+    /// let nearest = tree.nearest_one(&[1.0, 2.0, 5.1], &squared_euclidean)?;
+    ///
+    /// assert!((nearest.0 - 0.01f64).abs() < f64::EPSILON);
+    /// assert_eq!(*nearest.1, 100);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    // TODO: pending only ever gets to about 7 items max. try doing this
+    //       recursively to avoid the alloc/dealloc of the vec
+    pub fn nearest_one<F>(&self, point: &[A; K], distance: &F) -> Result<(A, &T), ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.nearest_one_unchecked(&point, distance),
+            Some(skip) => {
+                self.nearest_one_unchecked(&point, &util::skip_dimensions(distance, skip))
+            }
+        }
+    }
+
+    fn nearest_one_unchecked<F>(&self, point: &[A; K], distance: &F) -> Result<(A, &T), ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        if self.searchable_size() == 0 {
+            return Err(ErrorKind::Empty);
+        }
+
+        let mut pending = Vec::with_capacity(16);
+
+        let mut best_dist: A = A::infinity();
+        let mut best_elem: Option<&T> = None;
+
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::zero(), &mut pending, |pending| {
+            self.nearest_one_step(
+                point,
+                &side_distance,
+                pending,
+                &mut best_dist,
+                &mut best_elem,
+            )
+        });
+
+        while !pending.is_empty() && (best_elem.is_none() || (pending[0].distance < best_dist)) {
+            self.nearest_one_step(
+                point,
+                distance,
+                &mut pending,
+                &mut best_dist,
+                &mut best_elem,
+            );
+        }
+
+        Ok((best_dist, best_elem.unwrap()))
+    }
+
+    fn within_impl<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+        recorder: &mut impl QueryRecorder,
+    ) -> Result<BinaryHeap<HeapElement<A, &T>>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut pending = BinaryHeap::new();
+        let mut evaluated = BinaryHeap::new();
+        self.within_into(
+            point,
+            radius,
+            distance,
+            &mut pending,
+            &mut evaluated,
+            recorder,
+        )?;
+
+        Ok(evaluated)
+    }
+
+    /// Runs a `within()` query using the supplied buffers, which are cleared first, leaving
+    /// the results unsorted in `evaluated`.
+    pub(crate) fn within_into<'a, F>(
+        &'a self,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+        pending: &mut BinaryHeap<HeapElement<A, &'a Self>>,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'a T>>,
+        recorder: &mut impl QueryRecorder,
+    ) -> Result<(), ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => {
+                self.within_into_unchecked(&point, radius, distance, pending, evaluated, recorder)
+            }
+            Some(skip) => self.within_into_unchecked(
+                &point,
+                radius,
+                &util::skip_dimensions(distance, skip),
+                pending,
+                evaluated,
+                recorder,
+            ),
+        }
+        Ok(())
+    }
+
+    fn within_into_unchecked<'a, F>(
+        &'a self,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+        pending: &mut BinaryHeap<HeapElement<A, &'a Self>>,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'a T>>,
+        recorder: &mut impl QueryRecorder,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        pending.clear();
+        evaluated.clear();
+
+        let num = self.searchable_size();
+        if num == 0 {
+            return;
+        }
+
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::zero(), pending, |pending| {
+            self.nearest_step(
+                point,
+                num,
+                radius,
+                &side_distance,
+                &|_| true,
+                pending,
+                evaluated,
+                recorder,
+            )
+        });
+
+        while !pending.is_empty() && (-pending.peek().unwrap().distance <= radius) {
+            self.nearest_step(
+                point,
+                num,
+                radius,
+                distance,
+                &|_| true,
+                pending,
+                evaluated,
+                recorder,
+            );
+        }
+        recorder.prune_nodes(pending.len());
+    }
+
+    /// Queries the tree to find all elements within `radius` of `point`, using the specified
+    /// distance metric function. Results are returned sorted nearest-first
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let within = tree.within(&[1.0, 2.0, 5.0], 10f64, &squared_euclidean)?;
+    ///
+    /// assert_eq!(within.len(), 2);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn within<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        self.within_impl(point, radius, distance, &mut ())
+            .map(|evaluated| {
+                evaluated
+                    .into_sorted_vec()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect()
+            })
+    }
+
+    /// Queries the tree to find all elements within `radius` of `point`, as per `within()`,
+    /// additionally returning statistics on the cost of the query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::with_per_node_capacity(1)?;
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let (within, stats) = tree.within_with_stats(&[1.0, 2.0, 5.0], 10f64, &squared_euclidean)?;
+    ///
+    /// assert_eq!(within.len(), 2);
+    /// assert_eq!(stats.leaves_scanned, 2);
+    /// assert_eq!(stats.nodes_pruned, 1);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn within_with_stats<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+    ) -> Result<NeighboursWithStats<'_, A, T>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut stats = QueryStats::default();
+        let evaluations = Cell::new(0);
+        let distance = |a: &[A; K], b: &[A; K]| {
+            evaluations.set(evaluations.get() + 1);
+            distance(a, b)
+        };
+
+        let evaluated = self.within_impl(point, radius, &distance, &mut stats)?;
+        stats.distance_evaluations = evaluations.get();
+        Ok((
+            evaluated
+                .into_sorted_vec()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            stats,
+        ))
+    }
+
+    /// Queries the tree to find all elements within `radius` of `point`, using the specified
+    /// distance metric function. Results are returned in arbitrary order. Faster than within()
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let within = tree.within(&[1.0, 2.0, 5.0], 10f64, &squared_euclidean)?;
+    ///
+    /// assert_eq!(within.len(), 2);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn within_unsorted<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        self.within_impl(point, radius, distance, &mut ())
+            .map(|evaluated| evaluated.into_vec().into_iter().map(Into::into).collect())
+    }
+
+    /// Queries the tree to find all elements whose distance `d` from `point` satisfies
+    /// `r_min <= d < r_max`, using the specified distance metric function. Results are
+    /// returned sorted nearest-first
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let within_shell = tree.within_shell(&[1.0, 2.0, 5.0], 1f64, 10f64, &squared_euclidean)?;
+    ///
+    /// assert_eq!(within_shell, vec![(3.0, &101)]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn within_shell<F>(
+        &self,
+        point: &[A; K],
+        r_min: A,
+        r_max: A,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.within_shell_unchecked(&point, r_min, r_max, distance),
+            Some(skip) => self.within_shell_unchecked(
+                &point,
+                r_min,
+                r_max,
+                &util::skip_dimensions(distance, skip),
+            ),
+        }
+    }
+
+    fn within_shell_unchecked<F>(
+        &self,
+        point: &[A; K],
+        r_min: A,
+        r_max: A,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut evaluated = Vec::new();
+        self.for_each_in_side_list(point, distance, [false; K], |dist, d| {
+            if r_min <= dist && dist < r_max {
+                evaluated.push(HeapElement {
+                    distance: dist,
+                    element: d,
+                });
+            }
+        });
+
+        let mut stack = Vec::new();
+        if self.size > 0 {
+            stack.push(self);
+        }
+
+        while let Some(curr) = stack.pop() {
+            let min_dist =
+                util::distance_to_space(point, &curr.min_bounds, &curr.max_bounds, distance);
+            if min_dist >= r_max {
+                continue;
+            }
+            let max_dist =
+                util::distance_to_space_max(point, &curr.min_bounds, &curr.max_bounds, distance);
+            if max_dist < r_min {
+                continue;
+            }
+
+            match &curr.content {
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    for (p, range) in groups(points, ends) {
+                        let dist = distance(point, p);
+                        if r_min <= dist && dist < r_max {
+                            evaluated.extend(bucket[range].iter().map(|d| HeapElement {
+                                distance: dist,
+                                element: d,
+                            }));
+                        }
+                    }
+                }
+                Node::Stem { left, right, .. } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+
+        evaluated.sort();
+        Ok(evaluated.into_iter().map(Into::into).collect())
+    }
+
+    /// Queries the tree to find the best `n` elements within `radius` of `point`, using the specified
+    /// distance metric function. Results are returned in arbitrary order. 'Best' is determined by
+    /// performing a comparison of the elements using < (ie, std::ord::lt)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 1)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let best_n_within = tree.best_n_within(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean)?;
+    ///
+    /// assert_eq!(best_n_within[0], 1);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn best_n_within<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &F,
+    ) -> Result<Vec<T>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        T: Copy + Ord,
+    {
+        self.best_n_within_impl(point, radius, max_qty, distance, &mut ())
+    }
+
+    /// Queries the tree to find the best `n` elements within `radius` of `point`, as per
+    /// `best_n_within()`, additionally returning statistics on the cost of the query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 1)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let (best_n_within, stats) =
+    ///     tree.best_n_within_with_stats(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean)?;
+    ///
+    /// assert_eq!(best_n_within, vec![1]);
+    /// assert_eq!(stats.distance_evaluations, 3);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn best_n_within_with_stats<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &F,
+    ) -> Result<(Vec<T>, QueryStats), ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        T: Copy + Ord,
+    {
+        let mut stats = QueryStats::default();
+        let evaluations = Cell::new(0);
+        let distance = |a: &[A; K], b: &[A; K]| {
+            evaluations.set(evaluations.get() + 1);
+            distance(a, b)
+        };
+
+        let best_n_within =
+            self.best_n_within_impl(point, radius, max_qty, &distance, &mut stats)?;
+        stats.distance_evaluations = evaluations.get();
+        Ok((best_n_within, stats))
+    }
+
+    fn best_n_within_impl<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &F,
+        recorder: &mut impl QueryRecorder,
+    ) -> Result<Vec<T>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        T: Copy + Ord,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.best_n_within_unchecked(&point, radius, max_qty, distance, recorder),
+            Some(skip) => self.best_n_within_unchecked(
+                &point,
+                radius,
+                max_qty,
+                &util::skip_dimensions(distance, skip),
+                recorder,
+            ),
+        }
+    }
+
+    fn best_n_within_unchecked<F>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &F,
+        recorder: &mut impl QueryRecorder,
+    ) -> Result<Vec<T>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        T: Copy + Ord,
+    {
+        if self.searchable_size() == 0 || max_qty == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut pending = Vec::with_capacity(max_qty);
+        let mut evaluated = BinaryHeap::<T>::new();
+
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::zero(), &mut pending, |pending| {
+            Self::best_n_within_step(
+                point,
+                max_qty,
+                radius,
+                &side_distance,
+                pending,
+                &mut evaluated,
+                recorder,
+            )
+        });
+
+        while !pending.is_empty() {
+            Self::best_n_within_step(
+                point,
+                max_qty,
+                radius,
+                distance,
+                &mut pending,
+                &mut evaluated,
+                recorder,
+            );
+        }
+
+        Ok(evaluated.into_vec().into_iter().collect())
+    }
+
+    /// Queries the tree to find the best `n` elements within `radius` of `point`, using the specified
+    /// distance metric function. 'Best' is determined by performing a comparison of the elements
+    /// using < (ie, std::ord::lt). Returns an iterator, which yields results sorted best-first.
+    ///
+    /// The traversal is deferred until the first call to `next()`, but is not incremental. As
+    /// 'best' is unrelated to an element's position in the tree, any unvisited leaf within
+    /// `radius` could hold a better element than those found so far, so that first call visits
+    /// every such leaf before yielding anything. Later calls yield the remaining results
+    /// without visiting the tree again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 1)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let mut best_n_within_iter = tree.best_n_within_into_iter(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean)?;
+    /// let first = best_n_within_iter.next().unwrap();
+    ///
+    /// assert_eq!(first, 1);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn best_n_within_into_iter<'a, 'b, F>(
+        &'b self,
+        point: &'a [A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &'a F,
+    ) -> Result<BestNWithinIter<'a, 'b, A, T, F, K>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        T: Copy + Ord,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+
+        let mut pending = Vec::with_capacity(max_qty);
+        let mut side_list = None;
+        if self.searchable_size() > 0 && max_qty > 0 {
+            pending.push(HeapElement {
+                distance: A::zero(),
+                element: self,
+            });
+            side_list = self.searchable_side_list();
+        }
+
+        Ok(BestNWithinIter {
+            point,
+            skip,
+            radius,
+            max_qty,
+            distance,
+            side_list,
+            pending,
+            evaluated: BinaryHeap::new(),
+            results: None,
+        })
+    }
+
+    /// Queries the tree to find the best `n` elements within `radius` of `point`, using the specified
+    /// distance metric function. 'Best' is determined by comparing the keys returned by `key`
+    /// for each element, with smaller keys being better. Results are returned sorted best-first,
+    /// alongside their distance from `point`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, (&str, u32), 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], ("a", 100))?;
+    /// tree.add(&[2.0, 3.0, 6.0], ("b", 1))?;
+    /// tree.add(&[200.0, 300.0, 600.0], ("c", 0))?;
+    ///
+    /// let best = tree.best_n_within_by_key(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean, |item| item.1)?;
+    ///
+    /// assert_eq!(best, vec![(3.0, &("b", 1))]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn best_n_within_by_key<F, G, Key>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &F,
+        key: G,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        G: Fn(&T) -> Key,
+        Key: Ord,
+    {
+        self.best_n_within_by(point, radius, max_qty, distance, |_, item| key(item))
+    }
+
+    /// Queries the tree to find the best `n` elements within `radius` of `point`, using the specified
+    /// distance metric function. 'Best' is determined by comparing the scores returned by `score`
+    /// for each element's distance from `point` and data, with smaller scores being better.
+    /// Scores that cannot be compared (such as NaN) are treated as equal. Results are returned
+    /// sorted best-first, alongside their distance from `point`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, f64, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 10.0)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 1.0)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 0.0)?;
+    ///
+    /// let best = tree.best_n_within_by(&[1.0, 2.0, 5.0], 10f64, 1, &squared_euclidean, |dist, item| dist + item)?;
+    ///
+    /// assert_eq!(best, vec![(3.0, &1.0)]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn best_n_within_by<F, G, S>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &F,
+        score: G,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        G: Fn(A, &T) -> S,
+        S: PartialOrd,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.best_n_within_by_unchecked(&point, radius, max_qty, distance, score),
+            Some(skip) => self.best_n_within_by_unchecked(
+                &point,
+                radius,
+                max_qty,
+                &util::skip_dimensions(distance, skip),
+                score,
+            ),
+        }
+    }
+
+    fn best_n_within_by_unchecked<F, G, S>(
+        &self,
+        point: &[A; K],
+        radius: A,
+        max_qty: usize,
+        distance: &F,
+        score: G,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        G: Fn(A, &T) -> S,
+        S: PartialOrd,
+    {
+        if self.searchable_size() == 0 || max_qty == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut evaluated = BinaryHeap::<KeyedElement<S, (A, &T)>>::new();
+        let mut evaluate = |dist: A, d| {
+            if dist > radius {
+                return;
+            }
+            let element = KeyedElement {
+                key: score(dist, d),
+                element: (dist, d),
+            };
+            if evaluated.len() < max_qty {
+                evaluated.push(element);
+            } else {
+                let mut top = evaluated.peek_mut().unwrap();
+                if element < *top {
+                    *top = element;
+                }
+            }
+        };
+
+        self.for_each_in_side_list(point, distance, [false; K], &mut evaluate);
+
+        let mut pending = Vec::with_capacity(max_qty);
+        pending.push(HeapElement {
+            distance: A::zero(),
+            element: self,
+        });
+
+        while let Some(curr) = pending.pop() {
+            let mut curr = curr.element;
+            <KdTree<A, T, K>>::populate_pending(
+                point,
+                radius,
+                distance,
+                &mut pending,
+                &mut curr,
+                &mut (),
+            );
+
+            match &curr.content {
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    for (p, range) in groups(points, ends) {
+                        let dist = distance(point, p);
+                        for d in &bucket[range] {
+                            evaluate(dist, d);
+                        }
+                    }
+                }
+                Node::Stem { .. } => unreachable!(),
+            }
+        }
+
+        Ok(evaluated
+            .into_sorted_vec()
+            .into_iter()
+            .map(|x| x.element)
+            .collect())
+    }
+
+    fn best_n_within_step<'b, F>(
+        point: &[A; K],
+        max_qty: usize,
+        max_dist: A,
+        distance: &F,
+        pending: &mut Vec<HeapElement<A, &'b Self>>,
+        evaluated: &mut BinaryHeap<T>,
+        recorder: &mut impl QueryRecorder,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        T: Copy + Ord,
+    {
+        let curr = &mut &*pending.pop().unwrap().element;
+        <KdTree<A, T, K>>::populate_pending(point, max_dist, distance, pending, curr, recorder);
+        recorder.scan_leaf();
+
+        match &curr.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => {
+                for (p, range) in groups(points, ends) {
+                    if distance(point, p) <= max_dist {
+                        for d in &bucket[range] {
+                            if evaluated.len() < max_qty {
+                                evaluated.push(*d);
+                            } else {
+                                let mut top = evaluated.peek_mut().unwrap();
+                                if d < &top {
+                                    *top = *d;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Node::Stem { .. } => unreachable!(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn nearest_step<'b, F, P>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        max_dist: A,
+        distance: &F,
+        filter: &P,
+        pending: &mut BinaryHeap<HeapElement<A, &'b Self>>,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'b T>>,
+        recorder: &mut impl QueryRecorder,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        P: Fn(&T) -> bool,
+    {
+        let curr = &mut &*pending.pop().unwrap().element;
+        <KdTree<A, T, K>>::populate_pending(point, max_dist, distance, pending, curr, recorder);
+        recorder.scan_leaf();
+
+        match &curr.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => {
+                for (p, range) in groups(points, ends) {
+                    let dist = distance(point, p);
+                    if dist > max_dist {
+                        continue;
+                    }
+
+                    // Every element in the group is the same distance away, so once one fails
+                    // to displace the furthest result, the rest of the group cannot either.
+                    for element in bucket[range]
+                        .iter()
+                        .filter(|d| filter(d))
+                        .map(|d| HeapElement {
+                            distance: dist,
+                            element: d,
+                        })
+                    {
+                        if evaluated.len() < num {
+                            evaluated.push(element);
+                        } else {
+                            let mut top = evaluated.peek_mut().unwrap();
+                            if element < *top {
+                                *top = element;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Node::Stem { .. } => unreachable!(),
+        }
+    }
+
+    fn nearest_one_step<'b, F>(
+        &self,
+        point: &[A; K],
+        distance: &F,
+        pending: &mut Vec<HeapElement<A, &'b Self>>,
+        best_dist: &mut A,
+        best_elem: &mut Option<&'b T>,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let curr = &mut &*pending.pop().unwrap().element;
+        let evaluated_dist = *best_dist;
+        <KdTree<A, T, K>>::populate_pending(
+            point,
+            evaluated_dist,
+            distance,
+            pending,
+            curr,
+            &mut (),
+        );
+
+        match &curr.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => {
+                for (p, range) in groups(points, ends) {
+                    let dist = distance(point, p);
+                    if best_elem.is_none() || dist < *best_dist {
+                        *best_elem = Some(&bucket[range.start]);
+                        *best_dist = dist;
+                    }
+                }
+            }
+            Node::Stem { .. } => unreachable!(),
+        }
+    }
+
+    fn populate_pending<'a, F>(
+        point: &[A; K],
+        max_dist: A,
+        distance: &F,
+        pending: &mut impl Stack<HeapElement<A, &'a Self>>,
+        curr: &mut &'a Self,
+        recorder: &mut impl QueryRecorder,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        while let Node::Stem { left, right, .. } = &curr.content {
+            recorder.visit_node();
+            let candidate;
+            if curr.belongs_in_left(point) {
+                candidate = right;
+                *curr = left;
+            } else {
+                candidate = left;
+                *curr = right;
+            };
+
+            let candidate_to_space = util::distance_to_space(
+                point,
+                &candidate.min_bounds,
+                &candidate.max_bounds,
+                distance,
+            );
+
+            if candidate_to_space <= max_dist {
+                pending.stack_push(HeapElement {
+                    distance: candidate_to_space * -A::one(),
+                    element: &**candidate,
+                });
+            } else {
+                recorder.prune_nodes(1);
+            }
+        }
+        recorder.visit_node();
+    }
+
+    /// Returns an iterator over all elements in the tree, sorted nearest-first to the query point.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    ///
+    /// let mut nearest_iter = tree.iter_nearest(&[1.0, 2.0, 5.1], &squared_euclidean)?;
+    ///
+    /// let nearest_first = nearest_iter.next().unwrap();
+    ///
+    /// assert!((nearest_first.0 - 0.01f64).abs() < f64::EPSILON);
+    /// assert_eq!(*nearest_first.1, 100);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn iter_nearest<'a, 'b, F>(
+        &'b self,
+        point: &'a [A; K],
+        distance: &'a F,
+    ) -> Result<NearestIter<'a, 'b, A, T, F, K>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+
+        let mut pending = BinaryHeap::new();
+        pending.push(HeapElement {
+            distance: A::zero(),
+            element: self,
+        });
+
+        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
+        let skipped = skip.unwrap_or([false; K]);
+        self.for_each_in_side_list(&point, distance, skipped, |dist, d| {
+            evaluated.push(HeapElement {
+                distance: -dist,
+                element: d,
+            })
+        });
+
+        Ok(NearestIter {
+            point,
+            skip,
+            pending,
+            evaluated,
+            distance,
+        })
+    }
+
+    /// Queries the tree to find the farthest `num` elements from `point`, using the specified
+    /// distance metric function. Results are returned sorted farthest-first
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let farthest = tree.farthest(&[1.0, 2.0, 5.0], 1, &squared_euclidean)?;
+    ///
+    /// assert_eq!(farthest.len(), 1);
+    /// assert_eq!(*farthest[0].1, 102);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn farthest<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let (point, skip) = self.check_query_point(point)?;
+        match skip {
+            None => self.farthest_unchecked(&point, num, distance),
+            Some(skip) => {
+                self.farthest_unchecked(&point, num, &util::skip_dimensions(distance, skip))
+            }
+        }
+    }
+
+    fn farthest_unchecked<F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let num = std::cmp::min(num, self.searchable_size());
+        if num == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut pending = BinaryHeap::new();
+        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
+
+        let side_distance = util::skip_dimensions(distance, [false; K]);
+        self.push_roots(A::infinity(), &mut pending, |pending| {
+            self.farthest_step(point, num, &side_distance, pending, &mut evaluated)
+        });
+
+        while !pending.is_empty()
+            && (evaluated.len() < num
+                || (pending.peek().unwrap().distance >= -evaluated.peek().unwrap().distance))
+        {
+            self.farthest_step(point, num, distance, &mut pending, &mut evaluated);
+        }
+
+        Ok(evaluated
+            .into_sorted_vec()
+            .into_iter()
+            .take(num)
+            .map(|x| (-x.distance, x.element))
+            .collect())
+    }
+
+    fn farthest_step<'b, F>(
+        &self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+        pending: &mut BinaryHeap<HeapElement<A, &'b Self>>,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'b T>>,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut curr = pending.pop().unwrap().element;
+        let min_dist = if evaluated.len() < num {
+            A::neg_infinity()
+        } else {
+            -evaluated.peek().unwrap().distance
+        };
+
+        while let Node::Stem { left, right, .. } = &curr.content {
+            let left_to_space =
+                util::distance_to_space_max(point, &left.min_bounds, &left.max_bounds, distance);
+            let right_to_space =
+                util::distance_to_space_max(point, &right.min_bounds, &right.max_bounds, distance);
+
+            let (candidate, candidate_to_space) = if left_to_space > right_to_space {
+                curr = left;
+                (right, right_to_space)
+            } else {
+                curr = right;
+                (left, left_to_space)
+            };
+
+            if candidate_to_space >= min_dist {
+                pending.push(HeapElement {
+                    distance: candidate_to_space,
+                    element: &**candidate,
+                });
+            }
+        }
+
+        match &curr.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => {
+                for (p, range) in groups(points, ends) {
+                    let dist = -distance(point, p);
+                    for element in bucket[range].iter().map(|d| HeapElement {
+                        distance: dist,
+                        element: d,
+                    }) {
+                        if evaluated.len() < num {
+                            evaluated.push(element);
+                        } else {
+                            let mut top = evaluated.peek_mut().unwrap();
+                            if element < *top {
+                                *top = element;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Node::Stem { .. } => unreachable!(),
+        }
+    }
+
+    /// Finds, for every element stored in the tree, its `num` nearest other elements, using
+    /// the specified distance metric function. An element is never returned as its own
+    /// neighbour, although other elements with identical coordinates or data are.
+    ///
+    /// Each query is seeded with the contents of the leaf that holds the element, so that
+    /// pruning is effective from the start of the traversal. Results are returned in
+    /// leaf order, with each element's neighbours sorted nearest-first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    /// tree.add(&[3.0, 0.0], 102)?;
+    ///
+    /// let all_knn = tree.all_knn(1, &squared_euclidean);
+    ///
+    /// assert_eq!(all_knn.len(), 3);
+    /// assert!(all_knn.contains(&(&102, vec![(4.0, &101)])));
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn all_knn<F>(&self, num: usize, distance: &F) -> Vec<(&T, Vec<(A, &T)>)>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        self.leaves()
+            .into_iter()
+            .flat_map(|leaf| {
+                leaf.leaf_groups()
+                    .unwrap()
+                    .enumerate()
+                    .flat_map(move |(group, (point, range))| {
+                        leaf.all_knn_for(self, group, point, range, num, distance)
+                    })
+            })
+            .collect()
+    }
+
+    /// Finds, for every element stored in the tree, its `num` nearest other elements, as per
+    /// `all_knn()`, but spreads the queries over the rayon thread pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    /// tree.add(&[3.0, 0.0], 102)?;
+    ///
+    /// let all_knn = tree.par_all_knn(1, &squared_euclidean);
+    ///
+    /// assert_eq!(all_knn, tree.all_knn(1, &squared_euclidean));
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_all_knn<F>(&self, num: usize, distance: &F) -> Vec<(&T, Vec<(A, &T)>)>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A + Sync,
+        A: Send + Sync,
+        T: Sync,
+    {
+        use rayon::prelude::*;
+
+        let queries: Vec<(&Self, usize, &[A; K], Range<usize>)> = self
+            .leaves()
+            .into_iter()
+            .flat_map(|leaf| {
+                leaf.leaf_groups()
+                    .unwrap()
+                    .enumerate()
+                    .map(move |(group, (point, range))| (leaf, group, point, range))
+            })
+            .collect();
+
+        queries
+            .into_par_iter()
+            .map(|(leaf, group, point, range)| {
+                leaf.all_knn_for(self, group, point, range, num, distance)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn leaves(&self) -> Vec<&Self> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![self];
+
+        while let Some(curr) = stack.pop() {
+            match &curr.content {
+                Node::Leaf { .. } => leaves.push(curr),
+                Node::Stem { left, right, .. } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+
+        leaves
+    }
+
+    pub(crate) fn bounds(&self) -> (&[A; K], &[A; K]) {
+        (&self.min_bounds, &self.max_bounds)
+    }
+
+    pub(crate) fn children(&self) -> Option<(&Self, &Self)> {
+        match &self.content {
+            Node::Stem { left, right, .. } => Some((left, right)),
+            Node::Leaf { .. } => None,
+        }
+    }
+
+    pub(crate) fn split_plane(&self) -> Option<(usize, A)> {
+        match &self.content {
+            Node::Stem {
+                split_dimension,
+                split_value,
+                ..
+            } => Some((*split_dimension as usize, *split_value)),
+            Node::Leaf { .. } => None,
+        }
+    }
+
+    /// Returns the distinct points held by this node if it is a leaf, each with the range of
+    /// positions in the leaf of the elements stored at it.
+    pub(crate) fn leaf_groups(&self) -> Option<impl Iterator<Item = (&[A; K], Range<usize>)>> {
+        match &self.content {
+            Node::Leaf { points, ends, .. } => Some(groups(points, ends)),
+            Node::Stem { .. } => None,
+        }
+    }
+
+    pub(crate) fn leaf_items(&self) -> Option<impl Iterator<Item = (&[A; K], &T)>> {
+        match &self.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => Some(
+                groups(points, ends)
+                    .flat_map(move |(p, range)| bucket[range].iter().map(move |d| (p, d))),
+            ),
+            Node::Stem { .. } => None,
+        }
+    }
+
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&[A; K], &T)> {
+        self.leaves()
+            .into_iter()
+            .flat_map(|leaf| leaf.leaf_items().unwrap())
+    }
+
+    /// Calls `visit` for every element within `radius` of `point`, in arbitrary order,
+    /// passing its distance, its position in `entries()` and the element itself, and
+    /// stopping early if `visit` returns `false`. `stack` is cleared and used for the
+    /// traversal, so that it can be reused across queries.
+    pub(crate) fn for_each_within<'b, F, G>(
+        &'b self,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+        stack: &mut Vec<(&'b Self, usize)>,
+        mut visit: G,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+        G: FnMut(A, usize, &'b T) -> bool,
+    {
+        stack.clear();
+        if self.size > 0 {
+            stack.push((self, 0));
+        }
+
+        while let Some((curr, offset)) = stack.pop() {
+            match &curr.content {
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    for (p, range) in groups(points, ends) {
+                        let dist = distance(point, p);
+                        if dist > radius {
+                            continue;
+                        }
+                        for (idx, d) in range.clone().zip(bucket[range].iter()) {
+                            if !visit(dist, offset + idx, d) {
+                                return;
+                            }
+                        }
+                    }
+                }
+                Node::Stem { left, right, .. } => {
+                    let children = [(left, offset), (right, offset + left.size)];
+                    for &(child, child_offset) in children.iter() {
+                        let child_to_space = util::distance_to_space(
+                            point,
+                            &child.min_bounds,
+                            &child.max_bounds,
+                            distance,
+                        );
+                        if child.size > 0 && child_to_space <= radius {
+                            stack.push((child, child_offset));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Queries the tree to find all elements that would have `point` as their nearest
+    /// neighbour if it were added to the tree (the monochromatic reverse nearest neighbours
+    /// of `point`), using the specified distance metric function. An element is included
+    /// if `point` is at least as close to it as its nearest other element. Results are
+    /// returned sorted nearest-first.
+    ///
+    /// Subtrees with at least two elements are pruned whenever the distance between their
+    /// bounds' corners is smaller than their distance from `point`, as every element they
+    /// contain then has a neighbour that is closer than `point`.
+    /// Each remaining element is then checked by searching only as far from it as `point`
+    /// is, stopping at the first element found to be closer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 1> = KdTree::new();
+    ///
+    /// tree.add(&[0.0], 100)?;
+    /// tree.add(&[1.0], 101)?;
+    /// tree.add(&[4.0], 102)?;
+    ///
+    /// let reverse_nearest = tree.reverse_nearest(&[3.0], &squared_euclidean)?;
+    ///
+    /// assert_eq!(reverse_nearest, vec![(1.0, &102)]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn reverse_nearest<F>(
+        &self,
+        point: &[A; K],
+        distance: &F,
+    ) -> Result<Vec<(A, &T)>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let point = &self.check_point(point)?;
+
+        let mut evaluated = Vec::new();
+        let mut stack = Vec::new();
+        let mut search_stack = Vec::new();
+        if self.size > 0 {
+            stack.push((self, 0));
+        }
+
+        while let Some((curr, offset)) = stack.pop() {
+            if curr.size >= 2
+                && distance(&curr.min_bounds, &curr.max_bounds)
+                    < util::distance_to_space(point, &curr.min_bounds, &curr.max_bounds, distance)
+            {
+                continue;
+            }
+
+            match &curr.content {
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    for (p, range) in groups(points, ends) {
+                        let dist = distance(point, p);
+
+                        // Other elements at the same point, or a closer element in the same
+                        // leaf, rule the candidates out without a traversal. Otherwise, search
+                        // only as far as `dist` from them, and stop at the first element found
+                        // closer than `point`.
+                        if range.len() > 1 && distance(p, p) < dist {
+                            continue;
+                        }
+                        let closer_in_leaf = groups(points, ends)
+                            .any(|(q, other)| other != range && distance(p, q) < dist);
+                        if closer_in_leaf {
+                            continue;
+                        }
+
+                        let positions = offset + range.start..offset + range.end;
+                        let mut closer = false;
+                        self.for_each_within(
+                            p,
+                            dist,
+                            distance,
+                            &mut search_stack,
+                            |other_dist, pos, _| {
+                                closer = !positions.contains(&pos) && other_dist < dist;
+                                !closer
+                            },
+                        );
+                        if !closer {
+                            evaluated.extend(bucket[range].iter().map(|d| HeapElement {
+                                distance: dist,
+                                element: d,
+                            }));
+                        }
+                    }
+                }
+                Node::Stem { left, right, .. } => {
+                    stack.push((right, offset + left.size));
+                    stack.push((left, offset));
+                }
+            }
+        }
+
+        evaluated.sort();
+        Ok(evaluated.into_iter().map(Into::into).collect())
+    }
+
+    fn all_knn_for<'b, F>(
+        &'b self,
+        root: &'b Self,
+        group: usize,
+        point: &[A; K],
+        range: Range<usize>,
+        num: usize,
+        distance: &F,
+    ) -> Vec<(&'b T, Vec<(A, &'b T)>)>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let items = match &self.content {
+            Node::Leaf { bucket, .. } => &bucket[range],
+            Node::Stem { .. } => unreachable!(),
+        };
+
+        // The other elements at the same point are the nearest neighbours of each of them, so
+        // the traversal only needs to find as many more as they fall short of `num`.
+        let num = std::cmp::min(num, root.size - 1);
+        let others = num.saturating_sub(items.len() - 1);
+
+        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
+        if others > 0 {
+            self.all_knn_leaf_step(point, Some(group), others, distance, &mut evaluated);
+            root.all_knn_traverse(self, point, others, distance, &mut evaluated);
+        }
+
+        let neighbours: Vec<(A, &T)> = evaluated
+            .into_sorted_vec()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        let dist = distance(point, point);
+
+        items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let same_point = items
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != idx)
+                    .map(|(_, other)| (dist, other));
+                let matches = same_point
+                    .chain(neighbours.iter().copied())
+                    .take(num)
+                    .collect();
+                (item, matches)
+            })
+            .collect()
+    }
+
+    /// Continues an `all_knn()` query from this root for an element of `leaf`, whose own
+    /// contents have already been evaluated.
+    fn all_knn_traverse<'b, F>(
+        &'b self,
+        leaf: &'b Self,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'b T>>,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut pending = BinaryHeap::new();
+        pending.push(HeapElement {
+            distance: A::zero(),
+            element: self,
+        });
+
+        while !pending.is_empty()
+            && (evaluated.len() < num
+                || (-pending.peek().unwrap().distance <= evaluated.peek().unwrap().distance))
+        {
+            let max_dist = if evaluated.len() < num {
+                A::infinity()
+            } else {
+                evaluated.peek().unwrap().distance
+            };
+
+            let mut curr = pending.pop().unwrap().element;
+            <KdTree<A, T, K>>::populate_pending(
+                point,
+                max_dist,
+                distance,
+                &mut pending,
+                &mut curr,
+                &mut (),
+            );
+
+            if !std::ptr::eq(curr, leaf) {
+                curr.all_knn_leaf_step(point, None, num, distance, evaluated);
+            }
+        }
+    }
+
+    fn all_knn_leaf_step<'b, F>(
+        &'b self,
+        point: &[A; K],
+        exclude: Option<usize>,
+        num: usize,
+        distance: &F,
+        evaluated: &mut BinaryHeap<HeapElement<A, &'b T>>,
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        match &self.content {
+            Node::Leaf {
+                points,
+                bucket,
+                ends,
+                ..
+            } => {
+                let iter = groups(points, ends)
+                    .enumerate()
+                    .filter(|(group, _)| Some(*group) != exclude);
+
+                for (_, (p, range)) in iter {
+                    let dist = distance(point, p);
+                    for element in &bucket[range] {
+                        let element = HeapElement {
+                            distance: dist,
+                            element,
+                        };
+                        if evaluated.len() < num {
+                            evaluated.push(element);
+                        } else {
+                            let mut top = evaluated.peek_mut().unwrap();
+                            if element < *top {
+                                *top = element;
+                            } else {
+                                // The rest of the group is the same distance away.
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Node::Stem { .. } => unreachable!(),
+        }
+    }
+
+    /// Finds, for every element stored in `other`, the nearest `num` elements in this tree,
+    /// using the specified distance metric function. Both trees are traversed together,
+    /// so that whole pairs of nodes can be pruned using their bounds. This is much faster
+    /// than calling `nearest()` for every element of `other` when both trees are large.
+    /// Results are returned in the leaf order of `other`, with each element's matches
+    /// sorted nearest-first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[5.0, 0.0], 101)?;
+    ///
+    /// let mut other: KdTree<f64, &str, 2> = KdTree::new();
+    /// other.add(&[1.0, 0.0], "a")?;
+    /// other.add(&[4.0, 0.0], "b")?;
+    ///
+    /// let matches = tree.knn_join(&other, 1, &squared_euclidean);
+    ///
+    /// assert_eq!(matches, vec![(&"a", vec![(1.0, &100)]), (&"b", vec![(1.0, &101)])]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn knn_join<'b, U, F>(
+        &'b self,
+        other: &'b KdTree<A, U, K>,
+        num: usize,
+        distance: &F,
+    ) -> Vec<(&'b U, Vec<(A, &'b T)>)>
+    where
+        U: PartialEq,
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let num = std::cmp::min(num, self.size);
+        let mut query = JoinNode::new(other, &mut 0);
+        let mut evaluated: Vec<BinaryHeap<HeapElement<A, &T>>> =
+            (0..other.size).map(|_| BinaryHeap::new()).collect();
+
+        if num > 0 {
+            self.knn_join_step(&mut query, num, distance, &mut evaluated);
+        }
+
+        // Only the first slot of each group of duplicates is filled in, and its matches are
+        // shared by the rest of the group.
+        let mut offset = 0;
+        let mut results = Vec::with_capacity(other.size);
+        for leaf in other.leaves() {
+            let bucket = match &leaf.content {
+                Node::Leaf { bucket, .. } => bucket,
+                Node::Stem { .. } => unreachable!(),
+            };
+            for (_, range) in leaf.leaf_groups().unwrap() {
+                let matches: Vec<(A, &T)> = std::mem::take(&mut evaluated[offset + range.start])
+                    .into_sorted_vec()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+                results.extend(bucket[range].iter().map(|item| (item, matches.clone())));
+            }
+            offset += leaf.size;
+        }
+        results
+    }
+
+    fn knn_join_step<'b, U, F>(
+        &'b self,
+        query: &mut JoinNode<'_, A, U, K>,
+        num: usize,
+        distance: &F,
+        evaluated: &mut Vec<BinaryHeap<HeapElement<A, &'b T>>>,
+    ) where
+        U: PartialEq,
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let node_dist = util::distance_between_spaces(
+            &query.tree.min_bounds,
+            &query.tree.max_bounds,
+            &self.min_bounds,
+            &self.max_bounds,
+            distance,
+        );
+        if self.size == 0 || query.tree.size == 0 || node_dist > query.bound {
+            return;
+        }
+
+        match (&mut query.children, &self.content) {
+            (
+                None,
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                },
+            ) => {
+                let offset = query.offset;
+                let mut bound = A::neg_infinity();
+                for (query_point, query_range) in query.tree.leaf_groups().unwrap() {
+                    let evaluated = &mut evaluated[offset + query_range.start];
+                    for (p, range) in groups(points, ends) {
+                        let dist = distance(query_point, p);
+                        for element in &bucket[range] {
+                            let element = HeapElement {
+                                distance: dist,
+                                element,
+                            };
+                            if evaluated.len() < num {
+                                evaluated.push(element);
+                            } else {
+                                let mut top = evaluated.peek_mut().unwrap();
+                                if element < *top {
+                                    *top = element;
+                                } else {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    bound = bound.max(if evaluated.len() < num {
+                        A::infinity()
+                    } else {
+                        evaluated.peek().unwrap().distance
+                    });
+                }
+                query.bound = bound;
+            }
+            (None, Node::Stem { .. }) => {
+                let (first, second) = self.order_children_by_distance(query.tree, distance);
+                first.knn_join_step(query, num, distance, evaluated);
+                second.knn_join_step(query, num, distance, evaluated);
+            }
+            (Some(children), _) => {
+                for child in children.iter_mut() {
+                    self.knn_join_step(child, num, distance, evaluated);
+                }
+                query.bound = children[0].bound.max(children[1].bound);
+            }
+        }
+    }
+
+    /// Finds, for every element stored in `other`, all elements of this tree within `radius`,
+    /// using the specified distance metric function. Both trees are traversed together, as
+    /// per `knn_join()`. Results are returned in the leaf order of `other`, with each
+    /// element's matches sorted nearest-first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[5.0, 0.0], 101)?;
+    ///
+    /// let mut other: KdTree<f64, &str, 2> = KdTree::new();
+    /// other.add(&[1.0, 0.0], "a")?;
+    /// other.add(&[9.0, 0.0], "b")?;
+    ///
+    /// let matches = tree.radius_join(&other, 4.0, &squared_euclidean);
+    ///
+    /// assert_eq!(matches, vec![(&"a", vec![(1.0, &100)]), (&"b", vec![])]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn radius_join<'b, U, F>(
+        &'b self,
+        other: &'b KdTree<A, U, K>,
+        radius: A,
+        distance: &F,
+    ) -> Vec<(&'b U, Vec<(A, &'b T)>)>
+    where
+        U: PartialEq,
+        F: Fn(&[A; K], &[A; K]) -> A,
     {
-        let curr = &mut &*pending.pop().unwrap().element;
-        <KdTree<A, T, K>>::populate_pending(point, max_dist, distance, pending, curr);
+        other
+            .entries()
+            .map(|(_, item)| item)
+            .zip(self.radius_join_positions(other, radius, distance))
+            .map(|(item, matches)| {
+                let matches = matches
+                    .into_iter()
+                    .map(|(dist, _, element)| (dist, element))
+                    .collect();
+                (item, matches)
+            })
+            .collect()
+    }
 
-        match &curr.content {
-            Node::Leaf { points, bucket, .. } => {
-                let points = points.iter();
-                let bucket = bucket.iter();
-                let iter = points.zip(bucket).map(|(p, d)| HeapElement {
-                    distance: distance(point, p),
-                    element: d,
-                });
+    /// Runs a `radius_join()`, returning the matches for each element of `other` in its
+    /// `entries()` order, with each match's position in this tree's `entries()`.
+    pub(crate) fn radius_join_positions<'b, U, F>(
+        &'b self,
+        other: &KdTree<A, U, K>,
+        radius: A,
+        distance: &F,
+    ) -> Vec<Vec<(A, usize, &'b T)>>
+    where
+        U: PartialEq,
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut evaluated: Vec<Vec<HeapElement<A, (usize, &T)>>> =
+            (0..other.size).map(|_| Vec::new()).collect();
 
-                for element in iter {
-                    if element <= max_dist {
-                        if evaluated.len() < max_qty {
-                            evaluated.push(*element.element);
-                        } else {
-                            let mut top = evaluated.peek_mut().unwrap();
-                            if element.element < &top {
-                                *top = *element.element;
-                            }
-                        }
-                    }
-                }
+        self.radius_join_step(0, other, 0, radius, distance, &mut evaluated);
+
+        // Only the first slot of each group of duplicates is filled in, and its matches are
+        // shared by the rest of the group.
+        let mut offset = 0;
+        let mut results = Vec::with_capacity(other.size);
+        for leaf in other.leaves() {
+            for (_, range) in leaf.leaf_groups().unwrap() {
+                let mut matches = std::mem::take(&mut evaluated[offset + range.start]);
+                matches.sort();
+                let matches: Vec<(A, usize, &T)> = matches
+                    .into_iter()
+                    .map(|e| (e.distance, e.element.0, e.element.1))
+                    .collect();
+                results.extend(std::iter::repeat_n(matches, range.len()));
             }
-            Node::Stem { .. } => unreachable!(),
+            offset += leaf.size;
         }
+        results
     }
 
-    fn nearest_step<'b, F>(
-        &self,
-        point: &[A; K],
-        num: usize,
-        max_dist: A,
+    fn radius_join_step<'b, U, F>(
+        &'b self,
+        self_offset: usize,
+        query: &KdTree<A, U, K>,
+        offset: usize,
+        radius: A,
         distance: &F,
-        pending: &mut BinaryHeap<HeapElement<A, &'b Self>>,
-        evaluated: &mut BinaryHeap<HeapElement<A, &'b T>>,
+        evaluated: &mut Vec<Vec<HeapElement<A, (usize, &'b T)>>>,
     ) where
+        U: PartialEq,
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        let curr = &mut &*pending.pop().unwrap().element;
-        <KdTree<A, T, K>>::populate_pending(point, max_dist, distance, pending, curr);
+        if self.size == 0 || query.size == 0 {
+            return;
+        }
 
-        match &curr.content {
-            Node::Leaf { points, bucket, .. } => {
-                let points = points.iter();
-                let bucket = bucket.iter();
-                let iter = points.zip(bucket).map(|(p, d)| HeapElement {
-                    distance: distance(point, p),
-                    element: d,
-                });
+        let node_dist = util::distance_between_spaces(
+            &query.min_bounds,
+            &query.max_bounds,
+            &self.min_bounds,
+            &self.max_bounds,
+            distance,
+        );
+        if node_dist > radius {
+            return;
+        }
 
-                for element in iter {
-                    if element <= max_dist {
-                        if evaluated.len() < num {
-                            evaluated.push(element);
-                        } else {
-                            let mut top = evaluated.peek_mut().unwrap();
-                            if element < *top {
-                                *top = element;
-                            }
+        match (&query.content, &self.content) {
+            (
+                Node::Leaf { .. },
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                },
+            ) => {
+                for (query_point, query_range) in query.leaf_groups().unwrap() {
+                    let evaluated = &mut evaluated[offset + query_range.start];
+                    for (p, range) in groups(points, ends) {
+                        let dist = distance(query_point, p);
+                        if dist <= radius {
+                            let positions = self_offset + range.start..self_offset + range.end;
+                            evaluated.extend(positions.zip(&bucket[range]).map(|element| {
+                                HeapElement {
+                                    distance: dist,
+                                    element,
+                                }
+                            }));
                         }
                     }
                 }
             }
-            Node::Stem { .. } => unreachable!(),
+            (Node::Leaf { .. }, Node::Stem { left, right, .. }) => {
+                let right_offset = self_offset + left.size;
+                left.radius_join_step(self_offset, query, offset, radius, distance, evaluated);
+                right.radius_join_step(right_offset, query, offset, radius, distance, evaluated);
+            }
+            (Node::Stem { left, right, .. }, _) => {
+                let right_offset = offset + left.size;
+                self.radius_join_step(self_offset, left, offset, radius, distance, evaluated);
+                self.radius_join_step(
+                    self_offset,
+                    right,
+                    right_offset,
+                    radius,
+                    distance,
+                    evaluated,
+                );
+            }
         }
     }
 
-    fn nearest_one_step<'b, F>(
+    /// Counts the pairs of elements, one from this tree and one from `other`, whose distance
+    /// falls into each of the bins delimited by `bin_edges`, using the specified distance
+    /// metric function. `bin_edges` must be sorted in ascending order, and bin `i` covers
+    /// distances `d` with `bin_edges[i] <= d < bin_edges[i + 1]`. Pairs outside of all bins
+    /// are not counted.
+    ///
+    /// Whole pairs of nodes are assigned to a bin without evaluating any distances whenever
+    /// the minimum and maximum distances between their bounds fall into the same bin.
+    /// If `other` is this same tree, every pair is counted twice and every element is also
+    /// paired with itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    ///
+    /// let mut data: KdTree<f64, usize, 2> = KdTree::new();
+    /// data.add(&[0.0, 0.0], 0)?;
+    /// data.add(&[3.0, 0.0], 1)?;
+    ///
+    /// let mut randoms: KdTree<f64, usize, 2> = KdTree::new();
+    /// randoms.add(&[1.0, 0.0], 0)?;
+    /// randoms.add(&[10.0, 0.0], 1)?;
+    ///
+    /// let counts = data.pair_counts(&randoms, &[0.0, 2.0, 5.0, 50.0], &squared_euclidean);
+    ///
+    /// assert_eq!(counts, vec![1, 1, 1]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn pair_counts<U, F>(
         &self,
-        point: &[A; K],
+        other: &KdTree<A, U, K>,
+        bin_edges: &[A],
         distance: &F,
-        pending: &mut Vec<HeapElement<A, &'b Self>>,
-        best_dist: &mut A,
-        best_elem: &mut Option<&'b T>,
+    ) -> Vec<usize>
+    where
+        U: PartialEq,
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let mut counts = vec![0; bin_edges.len().saturating_sub(1)];
+        if !counts.is_empty() {
+            self.pair_counts_step(other, bin_edges, distance, &mut counts);
+        }
+        counts
+    }
+
+    fn pair_counts_step<U, F>(
+        &self,
+        other: &KdTree<A, U, K>,
+        bin_edges: &[A],
+        distance: &F,
+        counts: &mut Vec<usize>,
     ) where
+        U: PartialEq,
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        let curr = &mut &*pending.pop().unwrap().element;
-        let evaluated_dist = *best_dist;
-        <KdTree<A, T, K>>::populate_pending(point, evaluated_dist, distance, pending, curr);
+        if self.size == 0 || other.size == 0 {
+            return;
+        }
 
-        match &curr.content {
-            Node::Leaf { points, bucket, .. } => {
-                let points = points.iter();
-                let bucket = bucket.iter();
-                let iter = points.zip(bucket).map(|(p, d)| HeapElement {
-                    distance: distance(point, p),
-                    element: d,
-                });
+        let bin_of = |dist: A| bin_edges.partition_point(|edge| *edge <= dist);
+
+        let min_bin = bin_of(util::distance_between_spaces(
+            &self.min_bounds,
+            &self.max_bounds,
+            &other.min_bounds,
+            &other.max_bounds,
+            distance,
+        ));
+        if min_bin == bin_edges.len() {
+            return;
+        }
+
+        let max_bin = bin_of(util::distance_between_spaces_max(
+            &self.min_bounds,
+            &self.max_bounds,
+            &other.min_bounds,
+            &other.max_bounds,
+            distance,
+        ));
+        if max_bin == 0 {
+            return;
+        }
+        if min_bin == max_bin {
+            counts[min_bin - 1] += self.size * other.size;
+            return;
+        }
 
-                for element in iter {
-                    if best_elem.is_none() || element < *best_dist {
-                        *best_elem = Some(element.element);
-                        *best_dist = element.distance;
+        match (&self.content, &other.content) {
+            (Node::Leaf { .. }, Node::Leaf { .. }) => {
+                for (p, range) in self.leaf_groups().unwrap() {
+                    for (q, other_range) in other.leaf_groups().unwrap() {
+                        let bin = bin_of(distance(p, q));
+                        if bin > 0 && bin < bin_edges.len() {
+                            counts[bin - 1] += range.len() * other_range.len();
+                        }
                     }
                 }
             }
-            Node::Stem { .. } => unreachable!(),
+            (Node::Stem { left, right, .. }, Node::Leaf { .. }) => {
+                left.pair_counts_step(other, bin_edges, distance, counts);
+                right.pair_counts_step(other, bin_edges, distance, counts);
+            }
+            (Node::Leaf { .. }, Node::Stem { left, right, .. }) => {
+                self.pair_counts_step(left, bin_edges, distance, counts);
+                self.pair_counts_step(right, bin_edges, distance, counts);
+            }
+            (Node::Stem { left, right, .. }, Node::Stem { .. }) if self.size >= other.size => {
+                left.pair_counts_step(other, bin_edges, distance, counts);
+                right.pair_counts_step(other, bin_edges, distance, counts);
+            }
+            (Node::Stem { .. }, Node::Stem { left, right, .. }) => {
+                self.pair_counts_step(left, bin_edges, distance, counts);
+                self.pair_counts_step(right, bin_edges, distance, counts);
+            }
         }
     }
 
-    fn populate_pending<'a, F>(
+    /// Estimates the density at `point` by summing the contributions of every element in the
+    /// tree, weighted by `kernel` applied to their distance from `point`, as returned by the
+    /// specified distance metric function, divided by `bandwidth`. As kernels are evaluated
+    /// on squared scaled distances, `bandwidth` is in the units returned by `distance`: for
+    /// `squared_euclidean`, pass the square of the euclidean bandwidth. The kernels are not
+    /// normalised, so the result should be scaled by the kernel's normalisation constant if
+    /// an actual probability density is needed.
+    ///
+    /// Subtrees whose contribution can be bounded to within `rel_error` of its true value
+    /// using their bounds are approximated as a whole, so the result is within a relative
+    /// error of `rel_error` of the exact sum. A `rel_error` of zero evaluates every element.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::kernel::Kernel;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    /// tree.add(&[5.0, 0.0], 102)?;
+    ///
+    /// let density = tree.density(&[0.0, 0.0], 4.0, Kernel::Epanechnikov, 0.0, &squared_euclidean)?;
+    ///
+    /// assert_eq!(density, 1.75);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn density<F>(
+        &self,
         point: &[A; K],
-        max_dist: A,
+        bandwidth: A,
+        kernel: Kernel,
+        rel_error: A,
         distance: &F,
-        pending: &mut impl Stack<HeapElement<A, &'a Self>>,
-        curr: &mut &'a Self,
-    ) where
+    ) -> Result<A, ErrorKind>
+    where
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        while let Node::Stem { left, right, .. } = &curr.content {
-            let candidate;
-            if curr.belongs_in_left(point) {
-                candidate = right;
-                *curr = left;
-            } else {
-                candidate = left;
-                *curr = right;
-            };
+        let point = self.check_point(point)?;
 
-            let candidate_to_space = util::distance_to_space(
+        let mut stack = Vec::new();
+        Ok(self.density_impl(&point, bandwidth, kernel, rel_error, distance, &mut stack))
+    }
+
+    /// Estimates the density at each of `points`, as per `density()`, reusing the traversal
+    /// buffers between queries.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::kernel::Kernel;
+    ///
+    /// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+    ///
+    /// tree.add(&[0.0, 0.0], 100)?;
+    /// tree.add(&[1.0, 0.0], 101)?;
+    ///
+    /// let queries = [[0.0, 0.0], [9.0, 9.0]];
+    /// let densities =
+    ///     tree.density_batch(&queries, 4.0, Kernel::Epanechnikov, 0.0, &squared_euclidean)?;
+    ///
+    /// assert_eq!(densities, vec![1.75, 0.0]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn density_batch<F>(
+        &self,
+        points: &[[A; K]],
+        bandwidth: A,
+        kernel: Kernel,
+        rel_error: A,
+        distance: &F,
+    ) -> Result<Vec<A>, ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let points = points
+            .iter()
+            .map(|point| self.check_point(point))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stack = Vec::new();
+        Ok(points
+            .iter()
+            .map(|point| {
+                self.density_impl(point, bandwidth, kernel, rel_error, distance, &mut stack)
+            })
+            .collect())
+    }
+
+    fn density_impl<'b, F>(
+        &'b self,
+        point: &[A; K],
+        bandwidth: A,
+        kernel: Kernel,
+        rel_error: A,
+        distance: &F,
+        stack: &mut Vec<&'b Self>,
+    ) -> A
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let weight = |dist: A| kernel.evaluate(dist / bandwidth);
+        let two = A::one() + A::one();
+
+        let mut total = A::zero();
+        stack.clear();
+        if self.size > 0 {
+            stack.push(self);
+        }
+
+        while let Some(curr) = stack.pop() {
+            let max_weight = weight(util::distance_to_space(
                 point,
-                &candidate.min_bounds,
-                &candidate.max_bounds,
+                &curr.min_bounds,
+                &curr.max_bounds,
                 distance,
-            );
+            ));
+            if max_weight <= A::zero() {
+                continue;
+            }
 
-            if candidate_to_space <= max_dist {
-                pending.stack_push(HeapElement {
-                    distance: candidate_to_space * -A::one(),
-                    element: &**candidate,
-                });
+            let min_weight = weight(util::distance_to_space_max(
+                point,
+                &curr.min_bounds,
+                &curr.max_bounds,
+                distance,
+            ));
+            let size = A::from(curr.size).unwrap();
+            if max_weight - min_weight <= two * rel_error * min_weight {
+                total = total + size * (max_weight + min_weight) / two;
+                continue;
+            }
+
+            match &curr.content {
+                Node::Leaf { .. } => {
+                    for (p, range) in curr.leaf_groups().unwrap() {
+                        let count = A::from(range.len()).unwrap();
+                        total = total + weight(distance(point, p)) * count;
+                    }
+                }
+                Node::Stem { left, right, .. } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
             }
         }
+
+        total
     }
 
-    /// Returns an iterator over all elements in the tree, sorted nearest-first to the query point.
+    fn order_children_by_distance<U, F>(
+        &self,
+        query: &KdTree<A, U, K>,
+        distance: &F,
+    ) -> (&Self, &Self)
+    where
+        U: PartialEq,
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        match &self.content {
+            Node::Stem { left, right, .. } => {
+                let left_dist = util::distance_between_spaces(
+                    &query.min_bounds,
+                    &query.max_bounds,
+                    &left.min_bounds,
+                    &left.max_bounds,
+                    distance,
+                );
+                let right_dist = util::distance_between_spaces(
+                    &query.min_bounds,
+                    &query.max_bounds,
+                    &right.min_bounds,
+                    &right.max_bounds,
+                    distance,
+                );
+                if left_dist <= right_dist {
+                    (left, right)
+                } else {
+                    (right, left)
+                }
+            }
+            Node::Leaf { .. } => unreachable!(),
+        }
+    }
+
+    /// Returns an iterator over all elements in the tree, sorted farthest-first from the query point.
     ///
     /// # Examples
     ///
@@ -704,38 +3600,198 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     /// tree.add(&[1.0, 2.0, 5.0], 100)?;
     /// tree.add(&[2.0, 3.0, 6.0], 101)?;
     ///
-    /// let mut nearest_iter = tree.iter_nearest(&[1.0, 2.0, 5.1], &squared_euclidean)?;
+    /// let mut farthest_iter = tree.iter_farthest(&[1.0, 2.0, 5.0], &squared_euclidean)?;
     ///
-    /// let nearest_first = nearest_iter.next().unwrap();
+    /// let farthest_first = farthest_iter.next().unwrap();
     ///
-    /// assert!((nearest_first.0 - 0.01f64).abs() < f64::EPSILON);
-    /// assert_eq!(*nearest_first.1, 100);
+    /// assert_eq!(farthest_first.0, 3f64);
+    /// assert_eq!(*farthest_first.1, 101);
     /// # Ok::<(), kiddo::ErrorKind>(())
     /// ```
-    pub fn iter_nearest<'a, 'b, F>(
+    pub fn iter_farthest<'a, 'b, F>(
         &'b self,
         point: &'a [A; K],
         distance: &'a F,
-    ) -> Result<NearestIter<'a, 'b, A, T, F, K>, ErrorKind>
+    ) -> Result<FarthestIter<'a, 'b, A, T, F, K>, ErrorKind>
     where
         F: Fn(&[A; K], &[A; K]) -> A,
     {
-        self.check_point(point)?;
+        let (point, skip) = self.check_query_point(point)?;
 
         let mut pending = BinaryHeap::new();
-        let evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
-
         pending.push(HeapElement {
-            distance: A::zero(),
+            distance: A::infinity(),
             element: self,
         });
 
-        Ok(NearestIter {
-            point,
-            pending,
-            evaluated,
-            distance,
-        })
+        let mut evaluated = BinaryHeap::<HeapElement<A, &T>>::new();
+        let skipped = skip.unwrap_or([false; K]);
+        self.for_each_in_side_list(&point, distance, skipped, |dist, d| {
+            evaluated.push(HeapElement {
+                distance: dist,
+                element: d,
+            })
+        });
+
+        Ok(FarthestIter {
+            point,
+            skip,
+            pending,
+            evaluated,
+            distance,
+        })
+    }
+
+    /// Returns an iterator over all elements located at exactly `point`. Only the single node
+    /// that `point` would be added to is searched, so no distances are computed.
+    ///
+    /// Under the `SideList` and `SkipDimension` policies, elements kept in the side list are
+    /// also returned if their coordinates match, with NaN matching NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[1.0, 2.0, 5.0], 101)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 102)?;
+    ///
+    /// let mut items: Vec<_> = tree.get_at(&[1.0, 2.0, 5.0]).collect();
+    /// items.sort();
+    ///
+    /// assert_eq!(items, vec![&100, &101]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn get_at<'a>(&'a self, point: &[A; K]) -> impl Iterator<Item = &'a T> + 'a {
+        let tree_items = match self.check_point(point) {
+            Ok(point) => self.items_at(&point),
+            Err(_) => &[],
+        };
+        let side_items = match &self.non_finite {
+            Some(non_finite) => non_finite.side.items_at(point),
+            None => &[],
+        };
+
+        tree_items.iter().chain(side_items.iter())
+    }
+
+    /// Returns an iterator over mutable references to all elements located at exactly
+    /// `point`, as per `get_at()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    ///
+    /// for item in tree.get_at_mut(&[1.0, 2.0, 5.0]) {
+    ///     *item += 1;
+    /// }
+    ///
+    /// assert_eq!(tree.get_at(&[1.0, 2.0, 5.0]).collect::<Vec<_>>(), vec![&101]);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn get_at_mut<'a>(&'a mut self, point: &[A; K]) -> impl Iterator<Item = &'a mut T> + 'a {
+        let tree_items = match self.check_point(point) {
+            Ok(point) => Self::items_at_mut(&mut self.content, &point),
+            Err(_) => &mut [],
+        };
+        let side_items = match &mut self.non_finite {
+            Some(non_finite) => Self::items_at_mut(&mut non_finite.side.content, point),
+            None => &mut [],
+        };
+
+        tree_items.iter_mut().chain(side_items.iter_mut())
+    }
+
+    /// Returns true if an element equal to `data` is located at exactly `point`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    ///
+    /// assert!(tree.contains(&[1.0, 2.0, 5.0], &100));
+    /// assert!(!tree.contains(&[1.0, 2.0, 5.0], &101));
+    /// assert!(!tree.contains(&[2.0, 3.0, 6.0], &100));
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn contains(&self, point: &[A; K], data: &T) -> bool {
+        self.get_at(point).any(|item| item == data)
+    }
+
+    /// Returns the elements of the leaf group located at exactly `point`, if any.
+    fn items_at(&self, point: &[A; K]) -> &[T] {
+        let mut curr = self;
+        loop {
+            match &curr.content {
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    return groups(points, ends)
+                        .find(|(p, _)| same_coordinates(p, point))
+                        .map_or(&[], |(_, range)| &bucket[range]);
+                }
+                Node::Stem {
+                    left,
+                    right,
+                    split_dimension,
+                    split_value,
+                } => {
+                    curr = if point[*split_dimension as usize] < *split_value {
+                        left
+                    } else {
+                        right
+                    };
+                }
+            }
+        }
+    }
+
+    /// Returns the elements of the leaf group located at exactly `point`, as per `items_at()`.
+    fn items_at_mut<'a>(content: &'a mut Node<A, T, K>, point: &[A; K]) -> &'a mut [T] {
+        let mut curr = content;
+        loop {
+            match curr {
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    return match groups(points, ends).find(|(p, _)| same_coordinates(p, point)) {
+                        Some((_, range)) => &mut bucket[range],
+                        None => &mut [],
+                    };
+                }
+                Node::Stem {
+                    left,
+                    right,
+                    split_dimension,
+                    split_value,
+                } => {
+                    curr = if point[*split_dimension as usize] < *split_value {
+                        &mut left.content
+                    } else {
+                        &mut right.content
+                    };
+                }
+            }
+        }
     }
 
     /// Add an element to the tree. The first argument specifies the location in kd space
@@ -756,8 +3812,14 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     /// # Ok::<(), kiddo::ErrorKind>(())
     /// ```
     pub fn add(&mut self, point: &[A; K], data: T) -> Result<(), ErrorKind> {
-        self.check_point(point)?;
-        self.add_unchecked(point, data)
+        if let Some(side) = self.side_list_for(point) {
+            side.size += 1;
+            Self::add_to_leaf(&mut side.content, point, data);
+            return Ok(());
+        }
+
+        let point = self.check_point(point)?;
+        self.add_unchecked(&point, data)
     }
 
     fn add_unchecked(&mut self, point: &[A; K], data: T) -> Result<(), ErrorKind> {
@@ -790,46 +3852,76 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
 
     fn add_to_bucket(&mut self, point: &[A; K], data: T) {
         self.extend(point);
-        let cap;
-        match &mut self.content {
+        self.size += 1;
+        if Self::add_to_leaf(&mut self.content, point, data) {
+            self.split();
+        }
+    }
+
+    /// Adds `data` to the group for `point` in a leaf, starting a new group if there is none,
+    /// and returns whether the leaf now holds more distinct points than its capacity.
+    fn add_to_leaf(content: &mut Node<A, T, K>, point: &[A; K], data: T) -> bool {
+        match content {
             Node::Leaf {
-                ref mut points,
-                ref mut bucket,
+                points,
+                bucket,
+                ends,
                 capacity,
             } => {
-                points.push(*point);
-                bucket.push(data);
-                cap = *capacity;
+                match points.iter().position(|p| same_coordinates(p, point)) {
+                    Some(group) => {
+                        bucket.insert(ends[group], data);
+                        for end in ends[group..].iter_mut() {
+                            *end += 1;
+                        }
+                    }
+                    None => {
+                        points.push(*point);
+                        bucket.push(data);
+                        ends.push(bucket.len());
+                    }
+                }
+                points.len() > *capacity
             }
             Node::Stem { .. } => unreachable!(),
         }
+    }
 
-        self.size += 1;
-        if self.size > cap {
-            self.split();
+    pub fn remove(&mut self, point: &[A; K], data: &T) -> Result<usize, ErrorKind> {
+        if let Some(side) = self.side_list_for(point) {
+            return side.remove_unchecked(point, data);
         }
+
+        let point = &self.check_point(point)?;
+        self.remove_unchecked(point, data)
     }
 
-    pub fn remove(&mut self, point: &[A; K], data: &T) -> Result<usize, ErrorKind> {
+    fn remove_unchecked(&mut self, point: &[A; K], data: &T) -> Result<usize, ErrorKind> {
         let mut removed = 0;
-        self.check_point(point)?;
 
         match &mut self.content {
             Node::Leaf {
-                ref mut points,
-                ref mut bucket,
+                points,
+                bucket,
+                ends,
                 ..
             } => {
-                let mut p_index = 0;
-                while p_index < self.size {
-                    if &points[p_index] == point && &bucket[p_index] == data {
-                        points.swap_remove(p_index);
-                        bucket.swap_remove(p_index);
-                        removed += 1;
-                        self.size -= 1;
-                    } else {
-                        p_index += 1;
+                if let Some(group) = points.iter().position(|p| same_coordinates(p, point)) {
+                    let start = if group == 0 { 0 } else { ends[group - 1] };
+                    for idx in (start..ends[group]).rev() {
+                        if &bucket[idx] == data {
+                            bucket.remove(idx);
+                            removed += 1;
+                        }
+                    }
+                    for end in ends[group..].iter_mut() {
+                        *end -= removed;
                     }
+                    if ends[group] == start {
+                        points.remove(group);
+                        ends.remove(group);
+                    }
+                    self.size -= removed;
                 }
             }
             Node::Stem {
@@ -837,13 +3929,13 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
                 ref mut right,
                 ..
             } => {
-                let right_removed = right.remove(point, data)?;
+                let right_removed = right.remove_unchecked(point, data)?;
                 if right_removed > 0 {
                     self.size -= right_removed;
                     removed += right_removed;
                 }
 
-                let left_removed = left.remove(point, data)?;
+                let left_removed = left.remove_unchecked(point, data)?;
                 if left_removed > 0 {
                     self.size -= left_removed;
                     removed += left_removed;
@@ -857,10 +3949,10 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
     fn split(&mut self) {
         match &mut self.content {
             Node::Leaf {
-                ref mut bucket,
-                ref mut points,
+                points,
+                bucket,
+                ends,
                 capacity,
-                ..
             } => {
                 let mut split_dimension: Option<usize> = None;
                 let mut max = A::zero();
@@ -880,14 +3972,16 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
                     let mut left = Box::new(KdTree::with_per_node_capacity(*capacity).unwrap());
                     let mut right = Box::new(KdTree::with_per_node_capacity(*capacity).unwrap());
 
-                    while !points.is_empty() {
-                        let point = points.swap_remove(0);
-                        let data = bucket.swap_remove(0);
-                        if point[split_dimension] < split_value {
+                    let mut items = std::mem::take(bucket).into_iter();
+                    for (point, range) in groups(points, ends) {
+                        let child = if point[split_dimension] < split_value {
                             // belongs_in_left
-                            left.add_to_bucket(&point, data);
+                            &mut left
                         } else {
-                            right.add_to_bucket(&point, data);
+                            &mut right
+                        };
+                        for data in items.by_ref().take(range.len()) {
+                            child.add_to_bucket(point, data);
                         }
                     }
 
@@ -927,13 +4021,188 @@ impl<A: Float + Zero + One, T: std::cmp::PartialEq, const K: usize> KdTree<A, T,
         }
     }
 
-    fn check_point(&self, point: &[A; K]) -> Result<(), ErrorKind> {
+    fn check_point(&self, point: &[A; K]) -> Result<[A; K], ErrorKind> {
+        let mut checked = *point;
+        for (dimension, coord) in checked.iter_mut().enumerate() {
+            if coord.is_finite() {
+                continue;
+            }
+            match self.non_finite_policy() {
+                NonFinitePolicy::Clamp { min, max } if !coord.is_nan() => {
+                    *coord = if coord.is_sign_positive() { max } else { min };
+                }
+                _ => {
+                    return Err(ErrorKind::NonFiniteCoordinate {
+                        dimension,
+                        value: coord.to_f64().unwrap_or(f64::NAN),
+                    })
+                }
+            }
+        }
+        Ok(checked)
+    }
+
+    /// Checks a query point as per `check_point()`, except under the `SkipDimension` policy.
+    /// Alongside the point, returns the dimensions to leave out of the distance metric if the
+    /// query point has non-finite coordinates, in which case every distance the query
+    /// evaluates needs `util::skip_dimensions()`. Otherwise, only the side list does.
+    fn check_query_point(&self, point: &[A; K]) -> Result<([A; K], Option<[bool; K]>), ErrorKind> {
+        if self.non_finite_policy() != NonFinitePolicy::SkipDimension {
+            return self.check_point(point).map(|checked| (checked, None));
+        }
         if point.iter().all(|n| n.is_finite()) {
-            Ok(())
-        } else {
-            Err(ErrorKind::NonFiniteCoordinate)
+            return Ok((*point, None));
+        }
+
+        let mut checked = *point;
+        let mut skip = [false; K];
+        for (coord, skipped) in checked.iter_mut().zip(skip.iter_mut()) {
+            if !coord.is_finite() {
+                *coord = A::zero();
+                *skipped = true;
+            }
+        }
+        Ok((checked, Some(skip)))
+    }
+
+    /// Returns the side list that `point` belongs in, if its policy keeps points with
+    /// non-finite coordinates and `point` has any.
+    fn side_list_for(&mut self, point: &[A; K]) -> Option<&mut Self> {
+        match &mut self.non_finite {
+            Some(non_finite)
+                if matches!(
+                    non_finite.policy,
+                    NonFinitePolicy::SkipDimension | NonFinitePolicy::SideList
+                ) && !point.iter().all(|n| n.is_finite()) =>
+            {
+                Some(&mut non_finite.side)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the side list if queries search it, as they do under the `SkipDimension`
+    /// policy, and it is not empty.
+    fn searchable_side_list(&self) -> Option<&Self> {
+        match &self.non_finite {
+            Some(non_finite)
+                if non_finite.policy == NonFinitePolicy::SkipDimension
+                    && non_finite.side.size > 0 =>
+            {
+                Some(&non_finite.side)
+            }
+            _ => None,
         }
     }
+
+    /// Returns the number of elements queries can return, including those in the side list
+    /// when it is searched.
+    fn searchable_size(&self) -> usize {
+        self.size + self.searchable_side_list().map_or(0, |side| side.size)
+    }
+
+    /// Pushes the tree itself onto `pending` at `distance`. When the side list is searched, it
+    /// is first pushed alone and `scan` is called to pop and scan it, so that the query can
+    /// scan it with a distance metric that leaves its points' non-finite dimensions out,
+    /// without paying for that on every point in the tree.
+    fn push_roots<'a, S>(&'a self, distance: A, pending: &mut S, scan: impl FnOnce(&mut S))
+    where
+        S: Stack<HeapElement<A, &'a Self>>,
+    {
+        if let Some(side) = self.searchable_side_list() {
+            pending.stack_push(HeapElement {
+                distance,
+                element: side,
+            });
+            scan(pending);
+        }
+        pending.stack_push(HeapElement {
+            distance,
+            element: self,
+        });
+    }
+
+    /// Calls `visit` with every element of the side list, when it is searched, and its
+    /// distance from `point`. Each stored point's non-finite dimensions are left out of
+    /// `distance`, along with the dimensions in `skip`.
+    fn for_each_in_side_list<'a, F>(
+        &'a self,
+        point: &[A; K],
+        distance: &F,
+        skip: [bool; K],
+        mut visit: impl FnMut(A, &'a T),
+    ) where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        let side = match self.searchable_side_list() {
+            Some(side) => side,
+            None => return,
+        };
+        let distance = util::skip_dimensions(distance, skip);
+        if let Node::Leaf {
+            points,
+            bucket,
+            ends,
+            ..
+        } = &side.content
+        {
+            for (p, range) in groups(points, ends) {
+                let dist = distance(point, p);
+                for d in &bucket[range] {
+                    visit(dist, d);
+                }
+            }
+        }
+    }
+}
+
+fn same_coordinates<A: Float, const K: usize>(a: &[A; K], b: &[A; K]) -> bool {
+    a.iter()
+        .zip(b.iter())
+        .all(|(a, b)| a == b || (a.is_nan() && b.is_nan()))
+}
+
+/// Checks that a leaf's items are split into one non-empty group for each of its points.
+fn validate_groups<A, T, const K: usize>(
+    points: &[[A; K]],
+    bucket: &[T],
+    ends: &[usize],
+    depth: usize,
+) -> Result<(), ValidationError> {
+    let grouped = ends.len() == points.len()
+        && ends.last().map_or(0, |end| *end) == bucket.len()
+        && ends
+            .iter()
+            .scan(0, |start, end| {
+                let non_empty = end > start;
+                *start = *end;
+                Some(non_empty)
+            })
+            .all(|non_empty| non_empty);
+    if !grouped {
+        return Err(ValidationError::BucketMismatch {
+            depth,
+            points: points.len(),
+            items: bucket.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Pairs each of a leaf's distinct points with the range of its bucket holding the elements
+/// stored at that point.
+fn groups<'a, A, const K: usize>(
+    points: &'a [[A; K]],
+    ends: &'a [usize],
+) -> impl Iterator<Item = (&'a [A; K], Range<usize>)> + 'a {
+    points
+        .iter()
+        .zip(ends.iter())
+        .scan(0, |start, (point, end)| {
+            let range = *start..*end;
+            *start = *end;
+            Some((point, range))
+        })
 }
 
 pub struct NearestIter<
@@ -944,24 +4213,25 @@ pub struct NearestIter<
     F: 'a + Fn(&[A; K], &[A; K]) -> A,
     const K: usize,
 > {
-    point: &'a [A; K],
+    point: [A; K],
+    skip: Option<[bool; K]>,
     pending: BinaryHeap<HeapElement<A, &'b KdTree<A, T, K>>>,
     evaluated: BinaryHeap<HeapElement<A, &'b T>>,
     distance: &'a F,
 }
 
-impl<'a, 'b, A: Float + Zero + One, T: 'b, F: 'a, const K: usize> Iterator
-    for NearestIter<'a, 'b, A, T, F, K>
+impl<'a, 'b, A: Float + Zero + One, T: 'b, F: 'a, const K: usize> NearestIter<'a, 'b, A, T, F, K>
 where
     F: Fn(&[A; K], &[A; K]) -> A,
     T: PartialEq,
 {
-    type Item = (A, &'b T);
-    fn next(&mut self) -> Option<(A, &'b T)> {
+    fn next_with<D>(&mut self, distance: &D) -> Option<(A, &'b T)>
+    where
+        D: Fn(&[A; K], &[A; K]) -> A,
+    {
         use util::distance_to_space;
 
-        let distance = self.distance;
-        let point = self.point;
+        let point = &self.point;
         while !self.pending.is_empty()
             && (self.evaluated.peek().map_or(A::infinity(), |x| -x.distance)
                 >= -self.pending.peek().unwrap().distance)
@@ -988,15 +4258,20 @@ where
             }
 
             match &curr.content {
-                Node::Leaf { points, bucket, .. } => {
-                    let points = points.iter();
-                    let bucket = bucket.iter();
-
-                    self.evaluated
-                        .extend(points.zip(bucket).map(|(p, d)| HeapElement {
-                            distance: -distance(point, p),
-                            element: d,
-                        }));
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    for (p, range) in groups(points, ends) {
+                        let dist = -distance(point, p);
+                        self.evaluated
+                            .extend(bucket[range].iter().map(|d| HeapElement {
+                                distance: dist,
+                                element: d,
+                            }));
+                    }
                 }
                 Node::Stem { .. } => unreachable!(),
             }
@@ -1005,16 +4280,290 @@ where
     }
 }
 
+impl<'a, 'b, A: Float + Zero + One, T: 'b, F: 'a, const K: usize> Iterator
+    for NearestIter<'a, 'b, A, T, F, K>
+where
+    F: Fn(&[A; K], &[A; K]) -> A,
+    T: PartialEq,
+{
+    type Item = (A, &'b T);
+    fn next(&mut self) -> Option<(A, &'b T)> {
+        match self.skip {
+            None => self.next_with(self.distance),
+            Some(skip) => self.next_with(&util::skip_dimensions(self.distance, skip)),
+        }
+    }
+}
+
+pub struct BestNWithinIter<
+    'a,
+    'b,
+    A: 'a + 'b + Float,
+    T: 'b + PartialEq,
+    F: 'a + Fn(&[A; K], &[A; K]) -> A,
+    const K: usize,
+> {
+    point: [A; K],
+    skip: Option<[bool; K]>,
+    radius: A,
+    max_qty: usize,
+    distance: &'a F,
+    side_list: Option<&'b KdTree<A, T, K>>,
+    pending: Vec<HeapElement<A, &'b KdTree<A, T, K>>>,
+    evaluated: BinaryHeap<T>,
+    results: Option<std::vec::IntoIter<T>>,
+}
+
+impl<'a, 'b, A: Float + Zero + One, T: 'b, F: 'a, const K: usize>
+    BestNWithinIter<'a, 'b, A, T, F, K>
+where
+    F: Fn(&[A; K], &[A; K]) -> A,
+    T: Copy + Ord,
+{
+    fn next_with<D>(&mut self, distance: &D) -> Option<T>
+    where
+        D: Fn(&[A; K], &[A; K]) -> A,
+    {
+        if self.results.is_none() {
+            if let Some(side) = self.side_list.take() {
+                self.pending.push(HeapElement {
+                    distance: A::zero(),
+                    element: side,
+                });
+                KdTree::best_n_within_step(
+                    &self.point,
+                    self.max_qty,
+                    self.radius,
+                    &util::skip_dimensions(distance, [false; K]),
+                    &mut self.pending,
+                    &mut self.evaluated,
+                    &mut (),
+                );
+            }
+
+            while !self.pending.is_empty() {
+                KdTree::best_n_within_step(
+                    &self.point,
+                    self.max_qty,
+                    self.radius,
+                    distance,
+                    &mut self.pending,
+                    &mut self.evaluated,
+                    &mut (),
+                );
+            }
+
+            let evaluated = std::mem::take(&mut self.evaluated);
+            self.results = Some(evaluated.into_sorted_vec().into_iter());
+        }
+
+        self.results.as_mut().unwrap().next()
+    }
+}
+
+impl<'a, 'b, A: Float + Zero + One, T: 'b, F: 'a, const K: usize> Iterator
+    for BestNWithinIter<'a, 'b, A, T, F, K>
+where
+    F: Fn(&[A; K], &[A; K]) -> A,
+    T: Copy + Ord,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        match self.skip {
+            None => self.next_with(self.distance),
+            Some(skip) => self.next_with(&util::skip_dimensions(self.distance, skip)),
+        }
+    }
+}
+
+pub struct FarthestIter<
+    'a,
+    'b,
+    A: 'a + 'b + Float,
+    T: 'b + PartialEq,
+    F: 'a + Fn(&[A; K], &[A; K]) -> A,
+    const K: usize,
+> {
+    point: [A; K],
+    skip: Option<[bool; K]>,
+    pending: BinaryHeap<HeapElement<A, &'b KdTree<A, T, K>>>,
+    evaluated: BinaryHeap<HeapElement<A, &'b T>>,
+    distance: &'a F,
+}
+
+impl<'a, 'b, A: Float + Zero + One, T: 'b, F: 'a, const K: usize> FarthestIter<'a, 'b, A, T, F, K>
+where
+    F: Fn(&[A; K], &[A; K]) -> A,
+    T: PartialEq,
+{
+    fn next_with<D>(&mut self, distance: &D) -> Option<(A, &'b T)>
+    where
+        D: Fn(&[A; K], &[A; K]) -> A,
+    {
+        use util::distance_to_space_max;
+
+        let point = &self.point;
+        while !self.pending.is_empty()
+            && (self
+                .evaluated
+                .peek()
+                .map_or(A::neg_infinity(), |x| x.distance)
+                <= self.pending.peek().unwrap().distance)
+        {
+            let mut curr = self.pending.pop().unwrap().element;
+            while let Node::Stem { left, right, .. } = &curr.content {
+                let left_to_space =
+                    distance_to_space_max(point, &left.min_bounds, &left.max_bounds, distance);
+                let right_to_space =
+                    distance_to_space_max(point, &right.min_bounds, &right.max_bounds, distance);
+
+                let (candidate, candidate_to_space) = if left_to_space > right_to_space {
+                    curr = left;
+                    (right, right_to_space)
+                } else {
+                    curr = right;
+                    (left, left_to_space)
+                };
+                self.pending.push(HeapElement {
+                    distance: candidate_to_space,
+                    element: &**candidate,
+                });
+            }
+
+            match &curr.content {
+                Node::Leaf {
+                    points,
+                    bucket,
+                    ends,
+                    ..
+                } => {
+                    for (p, range) in groups(points, ends) {
+                        let dist = distance(point, p);
+                        self.evaluated
+                            .extend(bucket[range].iter().map(|d| HeapElement {
+                                distance: dist,
+                                element: d,
+                            }));
+                    }
+                }
+                Node::Stem { .. } => unreachable!(),
+            }
+        }
+        self.evaluated.pop().map(|x| (x.distance, x.element))
+    }
+}
+
+impl<'a, 'b, A: Float + Zero + One, T: 'b, F: 'a, const K: usize> Iterator
+    for FarthestIter<'a, 'b, A, T, F, K>
+where
+    F: Fn(&[A; K], &[A; K]) -> A,
+    T: PartialEq,
+{
+    type Item = (A, &'b T);
+    fn next(&mut self) -> Option<(A, &'b T)> {
+        match self.skip {
+            None => self.next_with(self.distance),
+            Some(skip) => self.next_with(&util::skip_dimensions(self.distance, skip)),
+        }
+    }
+}
+
+struct JoinNode<'a, A, U: PartialEq, const K: usize> {
+    tree: &'a KdTree<A, U, K>,
+    children: Option<Box<[JoinNode<'a, A, U, K>; 2]>>,
+    offset: usize,
+    bound: A,
+}
+
+impl<'a, A: Float, U: PartialEq, const K: usize> JoinNode<'a, A, U, K> {
+    fn new(tree: &'a KdTree<A, U, K>, offset: &mut usize) -> Self {
+        let start = *offset;
+        let children = match &tree.content {
+            Node::Leaf { .. } => {
+                *offset += tree.size;
+                None
+            }
+            Node::Stem { left, right, .. } => {
+                let left = JoinNode::new(left, offset);
+                let right = JoinNode::new(right, offset);
+                Some(Box::new([left, right]))
+            }
+        };
+
+        JoinNode {
+            tree,
+            children,
+            offset: start,
+            bound: A::infinity(),
+        }
+    }
+}
+
 impl std::error::Error for ErrorKind {}
 
+impl std::error::Error for ValidationError {}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ValidationError::BucketMismatch {
+                depth,
+                points,
+                items,
+            } => write!(
+                f,
+                "KdTree validation error: leaf at depth {} does not group {} items by {} points",
+                depth, items, points
+            ),
+            ValidationError::SizeMismatch {
+                depth,
+                size,
+                actual,
+            } => write!(
+                f,
+                "KdTree validation error: node at depth {} has size {} but holds {} elements",
+                depth, size, actual
+            ),
+            ValidationError::OutOfBounds { depth, dimension } => write!(
+                f,
+                "KdTree validation error: point outside the bounds of node at depth {} in dimension {}",
+                depth, dimension
+            ),
+            ValidationError::WrongSideOfSplit { depth, dimension } => write!(
+                f,
+                "KdTree validation error: point on the wrong side of the split on dimension {} at depth {}",
+                dimension, depth
+            ),
+            ValidationError::InvalidSplitDimension { depth, dimension } => write!(
+                f,
+                "KdTree validation error: stem at depth {} splits on out of range dimension {}",
+                depth, dimension
+            ),
+            ValidationError::SideListNotLeaf => write!(
+                f,
+                "KdTree validation error: side list of non-finite points is not a single leaf"
+            ),
+        }
+    }
+}
+
 impl std::fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let reason = match *self {
-            ErrorKind::NonFiniteCoordinate => "non-finite coordinate",
-            ErrorKind::ZeroCapacity => "zero capacity",
-            ErrorKind::Empty => "invalid operation on empty tree",
-        };
-        write!(f, "KdTree error: {}", reason)
+        match *self {
+            ErrorKind::NonFiniteCoordinate { dimension, value } => write!(
+                f,
+                "KdTree error: non-finite coordinate {} in dimension {}",
+                value, dimension
+            ),
+            ErrorKind::ZeroCapacity => write!(f, "KdTree error: zero capacity"),
+            ErrorKind::CapacityOverflow { capacity } => {
+                write!(f, "KdTree error: capacity {} overflows", capacity)
+            }
+            ErrorKind::MismatchedConfiguration { reason } => {
+                write!(f, "KdTree error: mismatched configuration: {}", reason)
+            }
+            ErrorKind::Empty => write!(f, "KdTree error: invalid operation on empty tree"),
+        }
     }
 }
 
@@ -1023,6 +4572,8 @@ mod tests {
     extern crate rand;
     use super::KdTree;
     use super::Node;
+    use super::NonFinitePolicy;
+    use super::ValidationError;
 
     fn random_point() -> ([f64; 2], i32) {
         rand::random::<([f64; 2], i32)>()
@@ -1069,4 +4620,127 @@ mod tests {
         assert_eq!(tree.size(), capacity + 1);
         assert!(!tree.is_leaf());
     }
+
+    fn split_tree() -> KdTree<f64, i32, 2> {
+        let mut tree: KdTree<f64, i32, 2> = KdTree::with_per_node_capacity(2).unwrap();
+        tree.add(&[0.0, 0.0], 0).unwrap();
+        tree.add(&[1.0, 0.0], 1).unwrap();
+        tree.add(&[2.0, 0.0], 2).unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+        tree
+    }
+
+    #[test]
+    fn it_validates_random_trees_after_removals() {
+        let mut tree: KdTree<f64, i32, 2> = KdTree::with_per_node_capacity(4).unwrap();
+        let points: Vec<([f64; 2], i32)> = (0..500).map(|_| random_point()).collect();
+        for (pos, data) in points.iter() {
+            tree.add(pos, *data).unwrap();
+        }
+        for (pos, data) in points.iter().step_by(3) {
+            tree.remove(pos, data).unwrap();
+        }
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn it_detects_size_mismatches() {
+        let mut tree = split_tree();
+        tree.size = 4;
+        assert_eq!(
+            tree.validate(),
+            Err(ValidationError::SizeMismatch {
+                depth: 0,
+                size: 4,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn it_detects_bucket_mismatches() {
+        let mut tree = split_tree();
+        if let Node::Stem { right, .. } = &mut tree.content {
+            if let Node::Leaf { bucket, .. } = &mut right.content {
+                bucket.pop();
+            }
+        }
+        assert_eq!(
+            tree.validate(),
+            Err(ValidationError::BucketMismatch {
+                depth: 1,
+                points: 2,
+                items: 1
+            })
+        );
+    }
+
+    #[test]
+    fn it_detects_invalid_split_dimensions() {
+        let mut tree = split_tree();
+        if let Node::Stem {
+            split_dimension, ..
+        } = &mut tree.content
+        {
+            *split_dimension = 2;
+        }
+        assert_eq!(
+            tree.validate(),
+            Err(ValidationError::InvalidSplitDimension {
+                depth: 0,
+                dimension: 2
+            })
+        );
+    }
+
+    #[test]
+    fn it_detects_side_list_mismatches() {
+        let mut tree: KdTree<f64, i32, 2> =
+            KdTree::new().with_non_finite_policy(NonFinitePolicy::SideList);
+        tree.add(&[f64::NAN, 0.0], 0).unwrap();
+        tree.add(&[f64::NAN, 0.0], 1).unwrap();
+        tree.add(&[f64::INFINITY, 0.0], 2).unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+
+        let side = &mut tree.non_finite.as_mut().unwrap().side;
+        if let Node::Leaf { ends, .. } = &mut side.content {
+            ends[0] = 0;
+        }
+        assert_eq!(
+            tree.validate(),
+            Err(ValidationError::BucketMismatch {
+                depth: 0,
+                points: 2,
+                items: 3
+            })
+        );
+    }
+
+    #[test]
+    fn it_detects_points_out_of_bounds() {
+        let mut tree = split_tree();
+        tree.max_bounds = [1.5, 0.0];
+        assert_eq!(
+            tree.validate(),
+            Err(ValidationError::OutOfBounds {
+                depth: 0,
+                dimension: 0
+            })
+        );
+    }
+
+    #[test]
+    fn it_detects_points_on_the_wrong_side_of_a_split() {
+        let mut tree = split_tree();
+        if let Node::Stem { split_value, .. } = &mut tree.content {
+            *split_value = 1.5;
+        }
+        assert_eq!(
+            tree.validate(),
+            Err(ValidationError::WrongSideOfSplit {
+                depth: 0,
+                dimension: 0
+            })
+        );
+    }
 }
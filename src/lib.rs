@@ -73,11 +73,20 @@ extern crate serde;
 #[cfg_attr(feature = "serialize", macro_use)]
 extern crate serde_derive;
 
+pub mod astro;
+pub mod cluster;
 mod custom_serde;
 pub mod distance;
+pub mod export;
 mod heap_element;
+pub mod kernel;
 pub mod kiddo;
+pub mod searcher;
+pub mod stats;
 mod util;
+pub mod visit;
 
 pub use crate::kiddo::ErrorKind;
 pub use crate::kiddo::KdTree;
+pub use crate::kiddo::NonFinitePolicy;
+pub use crate::kiddo::ValidationError;
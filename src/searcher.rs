@@ -0,0 +1,188 @@
+//! Reusable query buffers. Each `KdTree::nearest()` or `KdTree::within()` call allocates
+//! its own working heaps and result vector. A `Searcher` owns these instead and holds on to
+//! them between queries, so that tight loops of queries against the same tree avoid
+//! allocating once the buffers have grown to size.
+
+use num_traits::{Float, One, Zero};
+use std::collections::BinaryHeap;
+
+use crate::heap_element::HeapElement;
+use crate::{ErrorKind, KdTree};
+
+/// Owns the buffers used while querying a `KdTree`, and returns results borrowed from them.
+///
+/// A `Searcher` is tied to the lifetime `'a` of the trees it queries, as the buffers hold
+/// references into them between calls. It can be used with any tree borrowed for `'a`.
+///
+/// # Examples
+///
+/// ```rust
+/// use kiddo::KdTree;
+/// use kiddo::distance::squared_euclidean;
+/// use kiddo::searcher::Searcher;
+///
+/// let mut tree: KdTree<f64, usize, 2> = KdTree::new();
+///
+/// tree.add(&[0.0, 0.0], 0)?;
+/// tree.add(&[1.0, 1.0], 1)?;
+/// tree.add(&[2.0, 2.0], 2)?;
+///
+/// let mut searcher = Searcher::new();
+///
+/// for query in &[[0.1, 0.1], [1.9, 1.9]] {
+///     let nearest = searcher.nearest(&tree, query, 1, &squared_euclidean)?;
+///     assert_eq!(nearest.len(), 1);
+/// }
+/// # Ok::<(), kiddo::ErrorKind>(())
+/// ```
+pub struct Searcher<'a, A, T: PartialEq, const K: usize> {
+    pending: BinaryHeap<HeapElement<A, &'a KdTree<A, T, K>>>,
+    evaluated: BinaryHeap<HeapElement<A, &'a T>>,
+    results: Vec<(A, &'a T)>,
+}
+
+impl<'a, A: Float + Zero + One, T: PartialEq, const K: usize> Searcher<'a, A, T, K> {
+    /// Creates a new `Searcher` with empty buffers, which grow as needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::searcher::Searcher;
+    ///
+    /// let searcher: Searcher<f64, usize, 3> = Searcher::new();
+    /// ```
+    pub fn new() -> Self {
+        Searcher {
+            pending: BinaryHeap::new(),
+            evaluated: BinaryHeap::new(),
+            results: Vec::new(),
+        }
+    }
+
+    /// Creates a new `Searcher` whose buffers can hold `capacity` results, and as many
+    /// pending nodes, before needing to grow.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::searcher::Searcher;
+    ///
+    /// let searcher: Searcher<f64, usize, 3> = Searcher::with_capacity(16);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Searcher {
+            pending: BinaryHeap::with_capacity(capacity),
+            evaluated: BinaryHeap::with_capacity(capacity),
+            results: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Queries `tree` to find the nearest `num` elements to `point`, as per
+    /// `KdTree::nearest()`, returning the results sorted nearest-first in a slice borrowed
+    /// from this `Searcher`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::searcher::Searcher;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    ///
+    /// let mut searcher = Searcher::new();
+    /// let nearest = searcher.nearest(&tree, &[1.0, 2.0, 5.1], 1, &squared_euclidean)?;
+    ///
+    /// assert!((nearest[0].0 - 0.01f64).abs() < f64::EPSILON);
+    /// assert_eq!(*nearest[0].1, 100);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn nearest<F>(
+        &mut self,
+        tree: &'a KdTree<A, T, K>,
+        point: &[A; K],
+        num: usize,
+        distance: &F,
+    ) -> Result<&[(A, &'a T)], ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        self.results.clear();
+        let num = tree.nearest_into(
+            point,
+            num,
+            distance,
+            &mut self.pending,
+            &mut self.evaluated,
+            &mut (),
+        )?;
+        self.collect_sorted(num);
+
+        Ok(&self.results)
+    }
+
+    /// Queries `tree` to find all elements within `radius` of `point`, as per
+    /// `KdTree::within()`, returning the results sorted nearest-first in a slice borrowed
+    /// from this `Searcher`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use kiddo::KdTree;
+    /// use kiddo::distance::squared_euclidean;
+    /// use kiddo::searcher::Searcher;
+    ///
+    /// let mut tree: KdTree<f64, usize, 3> = KdTree::new();
+    ///
+    /// tree.add(&[1.0, 2.0, 5.0], 100)?;
+    /// tree.add(&[2.0, 3.0, 6.0], 101)?;
+    /// tree.add(&[200.0, 300.0, 600.0], 102)?;
+    ///
+    /// let mut searcher = Searcher::new();
+    /// let within = searcher.within(&tree, &[1.0, 2.0, 5.0], 10f64, &squared_euclidean)?;
+    ///
+    /// assert_eq!(within.len(), 2);
+    /// # Ok::<(), kiddo::ErrorKind>(())
+    /// ```
+    pub fn within<F>(
+        &mut self,
+        tree: &'a KdTree<A, T, K>,
+        point: &[A; K],
+        radius: A,
+        distance: &F,
+    ) -> Result<&[(A, &'a T)], ErrorKind>
+    where
+        F: Fn(&[A; K], &[A; K]) -> A,
+    {
+        self.results.clear();
+        tree.within_into(
+            point,
+            radius,
+            distance,
+            &mut self.pending,
+            &mut self.evaluated,
+            &mut (),
+        )?;
+        self.collect_sorted(usize::MAX);
+
+        Ok(&self.results)
+    }
+
+    /// Moves up to `num` of the evaluated elements, nearest-first, into `results`. The
+    /// heap's storage is sorted in place and handed back, so no buffer is reallocated.
+    fn collect_sorted(&mut self, num: usize) {
+        let mut sorted = std::mem::take(&mut self.evaluated).into_sorted_vec();
+        self.results
+            .extend(sorted.drain(..).take(num).map(Into::<(A, &T)>::into));
+        self.evaluated = BinaryHeap::from(sorted);
+    }
+}
+
+impl<'a, A: Float + Zero + One, T: PartialEq, const K: usize> Default for Searcher<'a, A, T, K> {
+    fn default() -> Self {
+        Searcher::new()
+    }
+}
@@ -0,0 +1,48 @@
+//! Summary statistics describing the shape of a `KdTree`, as returned by `KdTree::stats()`,
+//! and the cost of individual queries, as returned by the `*_with_stats` queries.
+
+/// The shape of a `KdTree`, useful when tuning `with_per_node_capacity()`. Depths are
+/// measured in stems from the root, so a tree that has never been split has a depth of 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeStats {
+    /// The number of elements in the tree.
+    pub size: usize,
+    /// The depth of the shallowest leaf.
+    pub min_depth: usize,
+    /// The depth of the deepest leaf.
+    pub max_depth: usize,
+    /// The mean depth of the leaves.
+    pub mean_depth: f64,
+    /// The number of stem nodes.
+    pub stems: usize,
+    /// The number of leaf nodes.
+    pub leaves: usize,
+    /// `leaf_fill[n]` is the number of leaves holding `n` distinct points, for `n` up to the
+    /// fullest leaf in the tree. Elements sharing a point are counted once, as they are grouped
+    /// together. Leaves holding more than the per-node capacity are counted in
+    /// `over_capacity_leaves` instead.
+    pub leaf_fill: Vec<usize>,
+    /// The number of leaves holding more distinct points than the per-node capacity, which
+    /// only happens for trees deserialized with such leaves.
+    pub over_capacity_leaves: usize,
+    /// `split_dimensions[d]` is the number of stems that split on dimension `d`.
+    pub split_dimensions: Vec<usize>,
+    /// An estimate of the heap memory used by the tree's nodes and their buffers, in bytes.
+    pub memory_bytes: usize,
+}
+
+/// The cost of a single query, as returned by `KdTree::nearest_with_stats()`,
+/// `KdTree::within_with_stats()` and `KdTree::best_n_within_with_stats()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    /// The number of stems and leaves the query descended into.
+    pub nodes_visited: usize,
+    /// The number of leaves whose points were compared against the query point.
+    pub leaves_scanned: usize,
+    /// The number of calls made to the distance metric, including those measuring the
+    /// distance to a node's bounding box.
+    pub distance_evaluations: usize,
+    /// The number of nodes skipped because they were too far from the query point to hold
+    /// any results.
+    pub nodes_pruned: usize,
+}
@@ -23,9 +23,110 @@ where
     distance(p1, &p2)
 }
 
+pub fn distance_to_space_max<F, T, const K: usize>(
+    p1: &[T; K],
+    min_bounds: &[T; K],
+    max_bounds: &[T; K],
+    distance: &F,
+) -> T
+where
+    F: Fn(&[T; K], &[T; K]) -> T,
+    T: Float,
+{
+    let mut p2 = [T::nan(); K];
+    for i in 0..K {
+        if (p1[i] - min_bounds[i]).abs() > (max_bounds[i] - p1[i]).abs() {
+            p2[i] = min_bounds[i];
+        } else {
+            p2[i] = max_bounds[i];
+        }
+    }
+    distance(p1, &p2)
+}
+
+pub fn distance_between_spaces<F, T, const K: usize>(
+    min_bounds_1: &[T; K],
+    max_bounds_1: &[T; K],
+    min_bounds_2: &[T; K],
+    max_bounds_2: &[T; K],
+    distance: &F,
+) -> T
+where
+    F: Fn(&[T; K], &[T; K]) -> T,
+    T: Float,
+{
+    let mut p1 = [T::nan(); K];
+    let mut p2 = [T::nan(); K];
+    for i in 0..K {
+        if max_bounds_1[i] < min_bounds_2[i] {
+            p1[i] = max_bounds_1[i];
+            p2[i] = min_bounds_2[i];
+        } else if max_bounds_2[i] < min_bounds_1[i] {
+            p1[i] = min_bounds_1[i];
+            p2[i] = max_bounds_2[i];
+        } else {
+            p1[i] = min_bounds_1[i].max(min_bounds_2[i]);
+            p2[i] = p1[i];
+        }
+    }
+    distance(&p1, &p2)
+}
+
+pub fn distance_between_spaces_max<F, T, const K: usize>(
+    min_bounds_1: &[T; K],
+    max_bounds_1: &[T; K],
+    min_bounds_2: &[T; K],
+    max_bounds_2: &[T; K],
+    distance: &F,
+) -> T
+where
+    F: Fn(&[T; K], &[T; K]) -> T,
+    T: Float,
+{
+    let mut p1 = [T::nan(); K];
+    let mut p2 = [T::nan(); K];
+    for i in 0..K {
+        if (max_bounds_1[i] - min_bounds_2[i]).abs() > (max_bounds_2[i] - min_bounds_1[i]).abs() {
+            p1[i] = max_bounds_1[i];
+            p2[i] = min_bounds_2[i];
+        } else {
+            p1[i] = min_bounds_1[i];
+            p2[i] = max_bounds_2[i];
+        }
+    }
+    distance(&p1, &p2)
+}
+
+/// Wraps `distance` so that the dimensions flagged in `skip`, along with any in which the
+/// second argument is non-finite, do not contribute to the distance from the query point,
+/// which is always the first argument.
+pub fn skip_dimensions<'a, F, T, const K: usize>(
+    distance: &'a F,
+    skip: [bool; K],
+) -> impl Fn(&[T; K], &[T; K]) -> T + 'a
+where
+    F: Fn(&[T; K], &[T; K]) -> T,
+    T: Float + 'a,
+{
+    move |query, other| {
+        let mut query = *query;
+        let mut other = *other;
+        for ((q, o), skipped) in query.iter_mut().zip(other.iter_mut()).zip(skip.iter()) {
+            if *skipped || !o.is_finite() {
+                *q = T::zero();
+                *o = T::zero();
+            }
+        }
+        distance(&query, &other)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::distance_to_space;
+    use super::{
+        distance_between_spaces, distance_between_spaces_max, distance_to_space,
+        distance_to_space_max,
+    };
     use crate::distance::squared_euclidean;
     use std::f64::{INFINITY, NEG_INFINITY};
 
@@ -73,4 +174,50 @@ mod tests {
         );
         assert_eq!(dis, 4.0);
     }
+
+    #[test]
+    fn test_normal_distance_to_space_max() {
+        let dis = distance_to_space_max(&[0.0, 0.0], &[1.0, 1.0], &[2.0, 3.0], &squared_euclidean);
+        assert_eq!(dis, 13.0);
+    }
+
+    #[test]
+    fn test_distance_inside_max() {
+        let dis = distance_to_space_max(&[1.0, 2.0], &[0.0, 0.0], &[3.0, 3.0], &squared_euclidean);
+        assert_eq!(dis, 8.0);
+    }
+
+    #[test]
+    fn test_distance_between_spaces() {
+        let min = [[0.0, 0.0], [2.0, 3.0]];
+        let max = [[1.0, 1.0], [4.0, 5.0]];
+        let dis = distance_between_spaces(&min[0], &max[0], &min[1], &max[1], &squared_euclidean);
+        assert_eq!(dis, 5.0);
+        let dis = distance_between_spaces(&min[1], &max[1], &min[0], &max[0], &squared_euclidean);
+        assert_eq!(dis, 5.0);
+    }
+
+    #[test]
+    fn test_distance_between_overlapping_spaces() {
+        let dis = distance_between_spaces(
+            &[0.0, 0.0],
+            &[2.0, 2.0],
+            &[1.0, 3.0],
+            &[3.0, 4.0],
+            &squared_euclidean,
+        );
+        assert_eq!(dis, 1.0);
+    }
+
+    #[test]
+    fn test_distance_between_spaces_max() {
+        let dis = distance_between_spaces_max(
+            &[0.0, 0.0],
+            &[1.0, 1.0],
+            &[2.0, 3.0],
+            &[4.0, 5.0],
+            &squared_euclidean,
+        );
+        assert_eq!(dis, 41.0);
+    }
 }
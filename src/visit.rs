@@ -0,0 +1,93 @@
+//! Read-only access to the nodes of a `KdTree`, for building traversals and queries outside
+//! of the crate. Start from `KdTree::root()` to walk the tree by hand, or implement `Visitor`
+//! and pass it to `KdTree::visit()`.
+
+use num_traits::Float;
+
+use crate::KdTree;
+
+/// A read-only cursor positioned at a node of a `KdTree`. Stems have `left()` and `right()`
+/// children and a `split()`, while leaves hold the elements, available through
+/// `leaf_items()`.
+#[derive(Debug)]
+pub struct NodeRef<'a, A, T: PartialEq, const K: usize> {
+    node: &'a KdTree<A, T, K>,
+}
+
+impl<'a, A, T: PartialEq, const K: usize> Clone for NodeRef<'a, A, T, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, A, T: PartialEq, const K: usize> Copy for NodeRef<'a, A, T, K> {}
+
+impl<'a, A: Float, T: PartialEq, const K: usize> NodeRef<'a, A, T, K> {
+    pub(crate) fn new(node: &'a KdTree<A, T, K>) -> Self {
+        NodeRef { node }
+    }
+
+    /// Returns the number of elements held by this node and its descendants.
+    pub fn size(&self) -> usize {
+        self.node.size()
+    }
+
+    /// Returns true if this node is a leaf.
+    pub fn is_leaf(&self) -> bool {
+        self.node.is_leaf()
+    }
+
+    /// Returns the minimum and maximum coordinates of the points added below this node. The
+    /// bounds are not shrunk when elements are removed, so may be larger than necessary.
+    pub fn bounds(&self) -> (&'a [A; K], &'a [A; K]) {
+        self.node.bounds()
+    }
+
+    /// Returns the dimension and value that this node splits on, or `None` for a leaf.
+    /// Points whose coordinate in that dimension is less than the value are held by the
+    /// left child, and all others by the right child.
+    pub fn split(&self) -> Option<(usize, A)> {
+        self.node.split_plane()
+    }
+
+    /// Returns the left child of this node, or `None` for a leaf.
+    pub fn left(&self) -> Option<Self> {
+        self.node.children().map(|(left, _)| NodeRef::new(left))
+    }
+
+    /// Returns the right child of this node, or `None` for a leaf.
+    pub fn right(&self) -> Option<Self> {
+        self.node.children().map(|(_, right)| NodeRef::new(right))
+    }
+
+    /// Returns an iterator over the points and elements held by this node if it is a leaf,
+    /// or `None` for a stem.
+    pub fn leaf_items(&self) -> Option<impl Iterator<Item = (&'a [A; K], &'a T)>> {
+        self.node.leaf_items()
+    }
+
+    pub(crate) fn accept<V>(self, visitor: &mut V)
+    where
+        V: Visitor<'a, A, T, K>,
+    {
+        if visitor.enter(self) {
+            if let (Some(left), Some(right)) = (self.left(), self.right()) {
+                left.accept(visitor);
+                right.accept(visitor);
+            }
+        }
+        visitor.leave(self);
+    }
+}
+
+/// Hooks called by `KdTree::visit()` as it walks the tree.
+pub trait Visitor<'a, A, T: PartialEq, const K: usize> {
+    /// Called when `node` is reached, before any of its children. Returning `false` skips
+    /// the children of `node`.
+    fn enter(&mut self, _node: NodeRef<'a, A, T, K>) -> bool {
+        true
+    }
+
+    /// Called once `node` and any of its children have been visited.
+    fn leave(&mut self, _node: NodeRef<'a, A, T, K>) {}
+}
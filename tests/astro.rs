@@ -0,0 +1,99 @@
+extern crate kiddo;
+
+use kiddo::astro;
+
+#[test]
+fn within_arcsec_returns_separations() {
+    let tree = astro::build_tree(vec![
+        (0.0f64, 0.0, 0),
+        (0.0, 1.0 / 3600.0, 1),
+        (1.0 / 3600.0, 0.0, 2),
+        (0.0, 10.0 / 3600.0, 3),
+        (359.9999, 0.0, 4),
+    ])
+    .unwrap();
+
+    let within = astro::within_arcsec(&tree, 0.0, 0.0, 1.5).unwrap();
+    let items: Vec<usize> = within.iter().map(|(_, item)| **item).collect();
+    assert_eq!(items.len(), 4);
+    assert_eq!(&items[..2], &[0, 4]);
+    assert!(items.contains(&1) && items.contains(&2));
+    for (sep, item) in within.iter().skip(2) {
+        assert!((sep - 1.0).abs() < 1e-6, "item {} at {}", item, sep);
+    }
+
+    let wrapped = astro::within_arcsec(&tree, 0.0, 0.0, 0.5).unwrap();
+    assert_eq!(wrapped.len(), 2);
+    assert!((wrapped[1].0 - 0.36).abs() < 1e-6);
+}
+
+#[test]
+fn cross_match_is_one_to_one() {
+    let catalogue = astro::build_tree(vec![
+        (150.0f64, 2.0, "a"),
+        (150.0, 2.0 + 2.0 / 3600.0, "b"),
+        (151.0, 2.0, "c"),
+    ])
+    .unwrap();
+    let other = astro::build_tree(vec![
+        (150.0f64, 2.0 + 0.5 / 3600.0, 1),
+        (150.0, 2.0 + 0.7 / 3600.0, 2),
+        (150.0, 2.0 + 2.2 / 3600.0, 3),
+    ])
+    .unwrap();
+
+    let matches = astro::cross_match(&catalogue, &other, 3.0).unwrap();
+
+    let pairs: Vec<(&str, usize)> = matches.iter().map(|(_, a, b)| (**a, **b)).collect();
+    assert_eq!(pairs, vec![("b", 3), ("a", 1)]);
+    assert!((matches[0].0 - 0.2).abs() < 1e-6);
+    assert!((matches[1].0 - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn cross_match_handles_zero_sized_items() {
+    let catalogue = astro::build_tree(vec![(150.0f64, 2.0, ()), (151.0, 2.0, ())]).unwrap();
+    let other = astro::build_tree(vec![
+        (150.0f64, 2.0 + 0.5 / 3600.0, ()),
+        (151.0, 2.0 + 0.5 / 3600.0, ()),
+    ])
+    .unwrap();
+
+    let matches = astro::cross_match(&catalogue, &other, 3.0).unwrap();
+
+    assert_eq!(matches.len(), 2);
+}
+
+#[test]
+fn rejects_non_finite_positions() {
+    assert_eq!(
+        astro::build_tree(vec![(f64::NAN, 0.0, 0)]).err(),
+        Some(kiddo::ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
+    );
+}
+
+#[test]
+fn rejects_trees_not_built_on_the_unit_sphere() {
+    let mut tree: kiddo::KdTree<f64, usize, 3> = kiddo::KdTree::new();
+    tree.add(&[10.0, 0.0, 0.0], 0).unwrap();
+    let catalogue = astro::build_tree(vec![(0.0f64, 0.0, 1)]).unwrap();
+
+    let expected = astro::AstroError::Tree(kiddo::ErrorKind::MismatchedConfiguration {
+        reason: "tree bounds extend beyond the unit sphere used by astro::build_tree",
+    });
+    assert_eq!(
+        astro::within_arcsec(&tree, 0.0, 0.0, 1.0).err(),
+        Some(expected)
+    );
+    assert_eq!(
+        astro::nearest_arcsec(&tree, 0.0, 0.0, 1).err(),
+        Some(expected)
+    );
+    assert_eq!(
+        astro::cross_match(&catalogue, &tree, 1.0).err(),
+        Some(expected)
+    );
+}
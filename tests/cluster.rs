@@ -0,0 +1,146 @@
+extern crate kiddo;
+
+use kiddo::cluster;
+use kiddo::distance::squared_euclidean;
+use kiddo::KdTree;
+
+fn blob(centre: [f64; 2], count: usize) -> Vec<[f64; 2]> {
+    (0..count)
+        .map(|_| {
+            let offset = rand::random::<[f64; 2]>();
+            [centre[0] + offset[0] * 0.1, centre[1] + offset[1] * 0.1]
+        })
+        .collect()
+}
+
+#[test]
+fn dbscan_separates_blobs_from_noise() {
+    let mut kdtree: KdTree<f64, (usize, usize), 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (blob_idx, centre) in [[0.0, 0.0], [10.0, 10.0]].iter().enumerate() {
+        for (idx, point) in blob(*centre, 50).iter().enumerate() {
+            kdtree.add(point, (blob_idx, idx)).unwrap();
+        }
+    }
+    kdtree.add(&[5.0, 5.0], (2, 0)).unwrap();
+    kdtree.add(&[-5.0, 5.0], (2, 1)).unwrap();
+
+    let labels = cluster::dbscan(&kdtree, 0.05, 4, &squared_euclidean);
+    assert_eq!(labels.len(), 102);
+
+    let label_of_blob = |blob_idx: usize| {
+        let blob_labels: Vec<Option<usize>> = labels
+            .iter()
+            .filter(|((b, _), _)| *b == blob_idx)
+            .map(|(_, label)| *label)
+            .collect();
+        assert!(blob_labels.iter().all(|label| *label == blob_labels[0]));
+        blob_labels[0]
+    };
+
+    let first = label_of_blob(0);
+    let second = label_of_blob(1);
+    assert!(first.is_some() && second.is_some());
+    assert_ne!(first, second);
+    assert_eq!(label_of_blob(2), None);
+}
+
+#[test]
+fn dbscan_with_min_pts_of_one_has_no_noise() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&[0.0, 0.0], 0).unwrap();
+    kdtree.add(&[0.0, 0.5], 1).unwrap();
+    kdtree.add(&[3.0, 3.0], 2).unwrap();
+
+    let mut labels = cluster::dbscan(&kdtree, 1.0, 1, &squared_euclidean);
+    labels.sort();
+    assert_eq!(labels[0].1, labels[1].1);
+    assert_ne!(labels[0].1, labels[2].1);
+    assert!(labels.iter().all(|(_, label)| label.is_some()));
+
+    let empty: KdTree<f64, usize, 2> = KdTree::new();
+    assert_eq!(cluster::dbscan(&empty, 1.0, 1, &squared_euclidean), vec![]);
+}
+
+#[test]
+fn dbscan_handles_zero_sized_items() {
+    let mut kdtree: KdTree<f64, (), 2> = KdTree::with_per_node_capacity(2).unwrap();
+    for point in [
+        [0.0, 0.0],
+        [0.0, 0.1],
+        [0.1, 0.0],
+        [5.0, 5.0],
+        [5.0, 5.1],
+        [5.1, 5.0],
+    ]
+    .iter()
+    {
+        kdtree.add(point, ()).unwrap();
+    }
+
+    let labels: Vec<Option<usize>> = cluster::dbscan(&kdtree, 0.05, 3, &squared_euclidean)
+        .into_iter()
+        .map(|(_, label)| label)
+        .collect();
+    let mut distinct = labels.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct, vec![Some(0), Some(1)]);
+    assert_eq!(labels.iter().filter(|&&label| label == Some(0)).count(), 3);
+}
+
+#[test]
+fn friends_of_friends_matches_brute_force() {
+    let points: Vec<[f64; 2]> = (0..300).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+
+    let linking_length = 0.002;
+    let groups = cluster::friends_of_friends(&kdtree, linking_length, &squared_euclidean);
+    assert_eq!(groups.len(), points.len());
+
+    let mut group_of = vec![0; points.len()];
+    for (idx, group) in groups {
+        group_of[*idx] = group;
+    }
+
+    let mut expected: Vec<usize> = (0..points.len()).collect();
+    fn root(parents: &[usize], idx: usize) -> usize {
+        let mut idx = idx;
+        while parents[idx] != idx {
+            idx = parents[idx];
+        }
+        idx
+    }
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            if squared_euclidean(&points[i], &points[j]) <= linking_length {
+                let (a, b) = (root(&expected, i), root(&expected, j));
+                expected[a] = b;
+            }
+        }
+    }
+
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            assert_eq!(
+                group_of[i] == group_of[j],
+                root(&expected, i) == root(&expected, j)
+            );
+        }
+    }
+}
+
+#[test]
+fn friends_of_friends_links_dense_subtrees() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    for idx in 0..20 {
+        kdtree.add(&[idx as f64 * 0.001, 0.0], idx).unwrap();
+    }
+    kdtree.add(&[10.0, 0.0], 20).unwrap();
+
+    let groups = cluster::friends_of_friends(&kdtree, 1.0, &squared_euclidean);
+    assert_eq!(groups.iter().filter(|(_, group)| *group == 0).count(), 20);
+    assert_eq!(groups.last(), Some(&(&20, 1)));
+}
@@ -70,3 +70,87 @@ fn it_works() {
     iter.next().unwrap();
     assert_eq!(count.swap(0, Ordering::SeqCst), 0);
 }
+
+#[test]
+fn with_stats_counts_match_distance_calls() {
+    let capacity_per_node = 2;
+    let mut kdtree = KdTree::with_per_node_capacity(capacity_per_node).unwrap();
+
+    let count = AtomicUsize::new(0);
+    let new_dist = |a: &[f64; 2], b: &[f64; 2]| {
+        count.fetch_add(1, Ordering::SeqCst);
+        squared_euclidean(a, b)
+    };
+
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    for num in 0..5 {
+        let (nearest, stats) = kdtree
+            .nearest_with_stats(&POINT_A.0, num, &new_dist)
+            .unwrap();
+        assert_eq!(
+            nearest,
+            kdtree.nearest(&POINT_A.0, num, &squared_euclidean).unwrap()
+        );
+        assert_eq!(stats.distance_evaluations, count.swap(0, Ordering::SeqCst));
+    }
+
+    for &radius in &[0.0, 1.0, 2.0] {
+        let (within, stats) = kdtree
+            .within_with_stats(&POINT_B.0, radius, &new_dist)
+            .unwrap();
+        assert_eq!(
+            within,
+            kdtree
+                .within(&POINT_B.0, radius, &squared_euclidean)
+                .unwrap()
+        );
+        assert_eq!(stats.distance_evaluations, count.swap(0, Ordering::SeqCst));
+    }
+
+    let (best, stats) = kdtree
+        .best_n_within_with_stats(&POINT_B.0, 2.0, 2, &new_dist)
+        .unwrap();
+    assert_eq!(
+        best,
+        kdtree
+            .best_n_within(&POINT_B.0, 2.0, 2, &squared_euclidean)
+            .unwrap()
+    );
+    assert_eq!(stats.distance_evaluations, count.swap(0, Ordering::SeqCst));
+}
+
+#[test]
+fn with_stats_reports_pruned_nodes() {
+    let mut kdtree = KdTree::with_per_node_capacity(1).unwrap();
+
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    let (nearest, stats) = kdtree
+        .nearest_with_stats(&POINT_A.0, 1, &squared_euclidean)
+        .unwrap();
+    assert_eq!(*nearest[0].1, 0);
+    assert_eq!(stats.leaves_scanned, 1);
+    assert!(stats.nodes_pruned > 0);
+    assert!(stats.nodes_visited > stats.leaves_scanned);
+
+    let (within, stats) = kdtree
+        .within_with_stats(&POINT_A.0, 100.0, &squared_euclidean)
+        .unwrap();
+    assert_eq!(within.len(), 4);
+    assert_eq!(stats.leaves_scanned, 4);
+    assert_eq!(stats.nodes_pruned, 0);
+
+    let empty: KdTree<f64, usize, 2> = KdTree::new();
+    let (within, stats) = empty
+        .within_with_stats(&POINT_A.0, 1.0, &squared_euclidean)
+        .unwrap();
+    assert!(within.is_empty());
+    assert_eq!(stats, Default::default());
+}
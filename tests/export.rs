@@ -0,0 +1,66 @@
+extern crate kiddo;
+
+use kiddo::export;
+use kiddo::KdTree;
+
+fn tree() -> KdTree<f64, usize, 2> {
+    let mut tree = KdTree::with_per_node_capacity(2).unwrap();
+    for (idx, point) in [[0.0, 0.0], [1.0, 1.0], [2.0, 0.5], [0.5, 2.0], [0.2, 0.3]]
+        .iter()
+        .enumerate()
+    {
+        tree.add(point, idx).unwrap();
+    }
+    tree
+}
+
+#[test]
+fn writes_dot_hierarchy() {
+    let tree = tree();
+    let stats = tree.stats();
+
+    let mut dot = Vec::new();
+    export::write_dot(&tree, &mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+
+    assert!(dot.starts_with("digraph kdtree {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("n0 [label=\"x0 < 1\\nsize 5\"];"));
+    assert_eq!(dot.matches("leaf\\nsize").count(), stats.leaves);
+    assert_eq!(dot.matches(" -> ").count(), 2 * stats.stems);
+}
+
+#[test]
+fn writes_svg_rendering() {
+    let tree = tree();
+    let stats = tree.stats();
+
+    let mut svg = Vec::new();
+    export::write_svg(&tree, &mut svg).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<circle").count(), tree.size());
+    assert_eq!(svg.matches("<line").count(), stats.stems);
+    assert_eq!(svg.matches("<rect").count(), stats.stems + stats.leaves);
+    assert!(svg.contains("<circle cx=\"0.5\" cy=\"-2\""));
+    assert!(!svg.contains("\"-0\""));
+}
+
+#[test]
+fn writes_empty_trees() {
+    let tree: KdTree<f64, usize, 2> = KdTree::new();
+
+    let mut dot = Vec::new();
+    export::write_dot(&tree, &mut dot).unwrap();
+    assert!(String::from_utf8(dot)
+        .unwrap()
+        .contains("n0 [label=\"leaf\\nsize 0\", style=rounded];"));
+
+    let mut svg = Vec::new();
+    export::write_svg(&tree, &mut svg).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+    assert_eq!(svg.matches("<rect").count(), 0);
+    assert_eq!(svg.matches("<circle").count(), 0);
+}
@@ -3,6 +3,7 @@ extern crate kiddo;
 use kiddo::distance::squared_euclidean;
 use kiddo::ErrorKind;
 use kiddo::KdTree;
+use kiddo::NonFinitePolicy;
 
 static POINT_A: ([f64; 2], usize) = ([0f64, 0f64], 0);
 static POINT_B: ([f64; 2], usize) = ([1f64, 1f64], 1);
@@ -158,19 +159,31 @@ fn handles_non_finite_coordinate() {
 
     assert_eq!(
         kdtree.add(&point_a.0, point_a.1),
-        Err(ErrorKind::NonFiniteCoordinate)
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
     );
     assert_eq!(
         kdtree.add(&point_b.0, point_b.1),
-        Err(ErrorKind::NonFiniteCoordinate)
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::INFINITY
+        })
     );
     assert_eq!(
         kdtree.nearest(&point_b.0, 1, &squared_euclidean),
-        Err(ErrorKind::NonFiniteCoordinate)
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::INFINITY
+        })
     );
     assert_eq!(
         kdtree.nearest(&point_a.0, 1, &squared_euclidean),
-        Err(ErrorKind::NonFiniteCoordinate)
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
     );
 }
 
@@ -384,8 +397,1056 @@ fn handles_remove_no_match() {
 
 #[test]
 fn error_messages_do_not_overflow_stack() {
-    format!("{}", ErrorKind::NonFiniteCoordinate);
+    format!(
+        "{}",
+        ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        }
+    );
     format!("{}", ErrorKind::ZeroCapacity);
     format!("{}", ErrorKind::Empty);
 }
 
+#[test]
+fn nearest_approx_respects_epsilon_bound() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for idx in 0..1000 {
+        kdtree.add(&rand::random::<[f64; 2]>(), idx).unwrap();
+    }
+
+    for _ in 0..50 {
+        let query = rand::random::<[f64; 2]>();
+        let exact = kdtree.nearest(&query, 5, &squared_euclidean).unwrap();
+
+        let approx_exact = kdtree
+            .nearest_approx(&query, 5, 0.0, &squared_euclidean)
+            .unwrap();
+        assert_eq!(approx_exact, exact);
+
+        let approx = kdtree
+            .nearest_approx(&query, 5, 0.5, &squared_euclidean)
+            .unwrap();
+        assert_eq!(approx.len(), 5);
+        for (approx, exact) in approx.iter().zip(exact.iter()) {
+            assert!(approx.0 <= exact.0 * 1.5);
+        }
+    }
+}
+
+#[test]
+fn nearest_approx_max_leaves_caps_leaf_visits() {
+    let mut kdtree = KdTree::with_per_node_capacity(1).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    assert_eq!(
+        kdtree
+            .nearest_approx_max_leaves(&POINT_A.0, 4, 0.0, 1, &squared_euclidean)
+            .unwrap(),
+        vec![(0f64, &0)]
+    );
+    assert_eq!(
+        kdtree
+            .nearest_approx_max_leaves(&POINT_A.0, 4, 0.0, 0, &squared_euclidean)
+            .unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        kdtree
+            .nearest_approx_max_leaves(&POINT_A.0, 4, 0.0, usize::MAX, &squared_euclidean)
+            .unwrap(),
+        kdtree.nearest(&POINT_A.0, 4, &squared_euclidean).unwrap()
+    );
+}
+
+#[test]
+fn farthest_matches_brute_force() {
+    let points: Vec<[f64; 2]> = (0..500).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+
+    for _ in 0..20 {
+        let query = rand::random::<[f64; 2]>();
+        let mut expected: Vec<f64> = points
+            .iter()
+            .map(|p| squared_euclidean(&query, p))
+            .collect();
+        expected.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+        let farthest: Vec<f64> = kdtree
+            .farthest(&query, 10, &squared_euclidean)
+            .unwrap()
+            .into_iter()
+            .map(|(d, _)| d)
+            .collect();
+        assert_eq!(farthest, expected[..10].to_vec());
+
+        let iterated: Vec<f64> = kdtree
+            .iter_farthest(&query, &squared_euclidean)
+            .unwrap()
+            .map(|(d, _)| d)
+            .collect();
+        assert_eq!(iterated, expected);
+    }
+}
+
+#[test]
+fn farthest_works() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    assert_eq!(
+        kdtree.farthest(&POINT_A.0, 0, &squared_euclidean).unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        kdtree.farthest(&POINT_A.0, 2, &squared_euclidean).unwrap(),
+        vec![(18f64, &3), (8f64, &2)]
+    );
+    assert_eq!(
+        kdtree.farthest(&POINT_A.0, 5, &squared_euclidean).unwrap(),
+        vec![(18f64, &3), (8f64, &2), (2f64, &1), (0f64, &0)]
+    );
+    assert_eq!(
+        kdtree.farthest(&[f64::NAN, 0f64], 1, &squared_euclidean),
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
+    );
+}
+
+#[test]
+fn all_knn_matches_brute_force() {
+    let points: Vec<[f64; 2]> = (0..300).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+
+    let all_knn = kdtree.all_knn(5, &squared_euclidean);
+    assert_eq!(all_knn.len(), points.len());
+
+    for (item, neighbours) in all_knn {
+        let mut expected: Vec<f64> = points
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| idx != item)
+            .map(|(_, p)| squared_euclidean(&points[*item], p))
+            .collect();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert!(neighbours.iter().all(|(_, neighbour)| *neighbour != item));
+        let distances: Vec<f64> = neighbours.into_iter().map(|(d, _)| d).collect();
+        assert_eq!(distances, expected[..5].to_vec());
+    }
+}
+
+#[test]
+fn all_knn_keeps_exact_duplicates() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+
+    let all_knn = kdtree.all_knn(1, &squared_euclidean);
+    assert_eq!(
+        all_knn,
+        vec![
+            (&0, vec![(0f64, &0)]),
+            (&0, vec![(0f64, &0)]),
+            (&1, vec![(2f64, &0)]),
+        ]
+    );
+
+    let single: KdTree<f64, usize, 2> = {
+        let mut tree = KdTree::new();
+        tree.add(&POINT_A.0, POINT_A.1).unwrap();
+        tree
+    };
+    assert_eq!(single.all_knn(3, &squared_euclidean), vec![(&0, vec![])]);
+}
+
+#[test]
+fn all_knn_handles_zero_sized_items() {
+    let mut kdtree: KdTree<f64, (), 2> = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, ()).unwrap();
+    kdtree.add(&POINT_B.0, ()).unwrap();
+    kdtree.add(&POINT_C.0, ()).unwrap();
+
+    let all_knn = kdtree.all_knn(2, &squared_euclidean);
+    assert_eq!(all_knn.len(), 3);
+    for (_, neighbours) in all_knn {
+        assert_eq!(neighbours.len(), 2);
+        assert!(neighbours.iter().all(|&(dist, _)| dist > 0f64));
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_all_knn_matches_all_knn() {
+    let mut kdtree: KdTree<f64, usize, 3> = KdTree::with_per_node_capacity(8).unwrap();
+    for idx in 0..500 {
+        kdtree.add(&rand::random::<[f64; 3]>(), idx).unwrap();
+    }
+
+    assert_eq!(
+        kdtree.par_all_knn(4, &squared_euclidean),
+        kdtree.all_knn(4, &squared_euclidean)
+    );
+}
+
+#[test]
+fn knn_join_matches_nearest() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for idx in 0..400 {
+        kdtree.add(&rand::random::<[f64; 2]>(), idx).unwrap();
+    }
+
+    let queries: Vec<[f64; 2]> = (0..150).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut other: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(3).unwrap();
+    for (idx, query) in queries.iter().enumerate() {
+        other.add(query, idx).unwrap();
+    }
+
+    let joined = kdtree.knn_join(&other, 3, &squared_euclidean);
+    assert_eq!(joined.len(), queries.len());
+    for (idx, matches) in joined {
+        assert_eq!(
+            matches,
+            kdtree
+                .nearest(&queries[*idx], 3, &squared_euclidean)
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn radius_join_matches_within() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for idx in 0..400 {
+        kdtree.add(&rand::random::<[f64; 2]>(), idx).unwrap();
+    }
+
+    let queries: Vec<[f64; 2]> = (0..150).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut other: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(3).unwrap();
+    for (idx, query) in queries.iter().enumerate() {
+        other.add(query, idx).unwrap();
+    }
+
+    let joined = kdtree.radius_join(&other, 0.01, &squared_euclidean);
+    assert_eq!(joined.len(), queries.len());
+    for (idx, matches) in joined {
+        let mut expected = kdtree
+            .within(&queries[*idx], 0.01, &squared_euclidean)
+            .unwrap();
+        let mut matches = matches;
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        matches.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(matches, expected);
+    }
+}
+
+#[test]
+fn joins_handle_empty_trees() {
+    let empty: KdTree<f64, usize, 2> = KdTree::new();
+    let mut kdtree = KdTree::new();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+
+    assert_eq!(kdtree.knn_join(&empty, 1, &squared_euclidean), vec![]);
+    assert_eq!(
+        empty.knn_join(&kdtree, 1, &squared_euclidean),
+        vec![(&0, vec![])]
+    );
+    assert_eq!(
+        empty.radius_join(&kdtree, 1.0, &squared_euclidean),
+        vec![(&0, vec![])]
+    );
+}
+
+#[test]
+fn pair_counts_matches_brute_force() {
+    let data: Vec<[f64; 2]> = (0..300).map(|_| rand::random::<[f64; 2]>()).collect();
+    let randoms: Vec<[f64; 2]> = (0..200).map(|_| rand::random::<[f64; 2]>()).collect();
+
+    let mut data_tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (idx, point) in data.iter().enumerate() {
+        data_tree.add(point, idx).unwrap();
+    }
+    let mut random_tree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (idx, point) in randoms.iter().enumerate() {
+        random_tree.add(point, idx).unwrap();
+    }
+
+    let bin_edges = [0.001, 0.01, 0.05, 0.1, 0.5];
+    let mut expected = vec![0; bin_edges.len() - 1];
+    for p in &data {
+        for q in &randoms {
+            let dist = squared_euclidean(p, q);
+            for bin in 0..expected.len() {
+                if bin_edges[bin] <= dist && dist < bin_edges[bin + 1] {
+                    expected[bin] += 1;
+                }
+            }
+        }
+    }
+
+    assert_eq!(
+        data_tree.pair_counts(&random_tree, &bin_edges, &squared_euclidean),
+        expected
+    );
+    assert_eq!(
+        data_tree.pair_counts(&random_tree, &bin_edges[..1], &squared_euclidean),
+        vec![]
+    );
+}
+
+#[test]
+fn pair_counts_self_counts_each_pair_twice() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    assert_eq!(
+        kdtree.pair_counts(&kdtree, &[0.0, 1.0, 4.0, 10.0, 20.0], &squared_euclidean),
+        vec![4, 6, 4, 2]
+    );
+}
+
+#[test]
+fn density_is_within_relative_error() {
+    use kiddo::kernel::Kernel;
+
+    let points: Vec<[f64; 2]> = (0..1000).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(8).unwrap();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+
+    let queries: Vec<[f64; 2]> = (0..20).map(|_| rand::random::<[f64; 2]>()).collect();
+    for kernel in [Kernel::Gaussian, Kernel::Epanechnikov].iter() {
+        let approx = kdtree
+            .density_batch(&queries, 0.04, *kernel, 0.05, &squared_euclidean)
+            .unwrap();
+        for (query, approx) in queries.iter().zip(approx) {
+            let expected: f64 = points
+                .iter()
+                .map(|p| kernel.evaluate(squared_euclidean(query, p) / 0.04))
+                .sum();
+
+            let exact = kdtree
+                .density(query, 0.04, *kernel, 0.0, &squared_euclidean)
+                .unwrap();
+            assert!((exact - expected).abs() <= 1e-9 * expected);
+            assert!((approx - expected).abs() <= 0.05 * expected);
+        }
+    }
+
+    assert_eq!(
+        kdtree.density(
+            &[f64::NAN, 0.0],
+            0.04,
+            Kernel::Gaussian,
+            0.0,
+            &squared_euclidean
+        ),
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
+    );
+}
+
+#[test]
+fn reverse_nearest_matches_brute_force() {
+    let points: Vec<[f64; 2]> = (0..300).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+
+    for _ in 0..20 {
+        let query = rand::random::<[f64; 2]>();
+        let mut expected: Vec<usize> = (0..points.len())
+            .filter(|&i| {
+                let dist = squared_euclidean(&query, &points[i]);
+                (0..points.len())
+                    .filter(|&j| j != i)
+                    .all(|j| dist <= squared_euclidean(&points[i], &points[j]))
+            })
+            .collect();
+        expected.sort_unstable();
+
+        let mut found: Vec<usize> = kdtree
+            .reverse_nearest(&query, &squared_euclidean)
+            .unwrap()
+            .into_iter()
+            .map(|(_, item)| *item)
+            .collect();
+        found.sort_unstable();
+
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn reverse_nearest_handles_small_trees() {
+    let mut kdtree = KdTree::new();
+    assert_eq!(
+        kdtree
+            .reverse_nearest(&POINT_A.0, &squared_euclidean)
+            .unwrap(),
+        vec![]
+    );
+
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+    assert_eq!(
+        kdtree
+            .reverse_nearest(&POINT_A.0, &squared_euclidean)
+            .unwrap(),
+        vec![(18f64, &3)]
+    );
+}
+
+#[test]
+fn nearest_excluding_skips_matching_items() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_A.0, 10).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    assert_eq!(
+        kdtree
+            .nearest_excluding(&POINT_A.0, 2, &squared_euclidean, &0)
+            .unwrap(),
+        vec![(0f64, &10), (2f64, &1)]
+    );
+    assert_eq!(
+        kdtree
+            .nearest_excluding(&POINT_A.0, 2, &squared_euclidean, &10)
+            .unwrap(),
+        vec![(0f64, &0), (2f64, &1)]
+    );
+    assert_eq!(
+        kdtree
+            .nearest_excluding(&POINT_A.0, 10, &squared_euclidean, &1)
+            .unwrap()
+            .len(),
+        4
+    );
+    assert_eq!(
+        kdtree.nearest_excluding(&[f64::NAN, 0f64], 1, &squared_euclidean, &0),
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
+    );
+}
+
+#[test]
+fn within_shell_matches_brute_force() {
+    let points: Vec<[f64; 2]> = (0..500).map(|_| rand::random::<[f64; 2]>()).collect();
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+
+    for _ in 0..20 {
+        let query = rand::random::<[f64; 2]>();
+        let mut expected: Vec<f64> = points
+            .iter()
+            .map(|p| squared_euclidean(&query, p))
+            .filter(|d| 0.05 <= *d && *d < 0.1)
+            .collect();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let found: Vec<f64> = kdtree
+            .within_shell(&query, 0.05, 0.1, &squared_euclidean)
+            .unwrap()
+            .into_iter()
+            .map(|(d, _)| d)
+            .collect();
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn within_shell_is_half_open() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    assert_eq!(
+        kdtree
+            .within_shell(&POINT_A.0, 2.0, 18.0, &squared_euclidean)
+            .unwrap(),
+        vec![(2f64, &1), (8f64, &2)]
+    );
+    assert_eq!(
+        kdtree
+            .within_shell(&POINT_A.0, 0.0, 2.0, &squared_euclidean)
+            .unwrap(),
+        vec![(0f64, &0)]
+    );
+    assert_eq!(
+        kdtree
+            .within_shell(&POINT_A.0, 8.0, 8.0, &squared_euclidean)
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn nearest_within_caps_distance() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+    kdtree.add(&POINT_D.0, POINT_D.1).unwrap();
+
+    assert_eq!(
+        kdtree
+            .nearest_within(&POINT_A.0, 4, 8.0, &squared_euclidean)
+            .unwrap(),
+        vec![(0f64, &0), (2f64, &1), (8f64, &2)]
+    );
+    assert_eq!(
+        kdtree
+            .nearest_within(&POINT_A.0, 2, 8.0, &squared_euclidean)
+            .unwrap(),
+        vec![(0f64, &0), (2f64, &1)]
+    );
+    assert_eq!(
+        kdtree
+            .nearest_within(&[-5f64, -5f64], 2, 8.0, &squared_euclidean)
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn nearest_within_matches_filtered_nearest() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    for idx in 0..500 {
+        kdtree.add(&rand::random::<[f64; 2]>(), idx).unwrap();
+    }
+
+    for _ in 0..20 {
+        let query = rand::random::<[f64; 2]>();
+        let expected: Vec<(f64, &usize)> = kdtree
+            .nearest(&query, 10, &squared_euclidean)
+            .unwrap()
+            .into_iter()
+            .filter(|(d, _)| *d <= 0.005)
+            .collect();
+        assert_eq!(
+            kdtree
+                .nearest_within(&query, 10, 0.005, &squared_euclidean)
+                .unwrap(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn best_n_within_by_key_ranks_by_key() {
+    let mut kdtree: KdTree<f64, (usize, u32), 2> = KdTree::with_per_node_capacity(4).unwrap();
+    let points: Vec<([f64; 2], u32)> = (0..500)
+        .map(|_| (rand::random::<[f64; 2]>(), rand::random::<u32>()))
+        .collect();
+    for (idx, (point, key)) in points.iter().enumerate() {
+        kdtree.add(point, (idx, *key)).unwrap();
+    }
+
+    let query = [0.5, 0.5];
+    let mut expected: Vec<(f64, (usize, u32))> = points
+        .iter()
+        .enumerate()
+        .map(|(idx, (p, key))| (squared_euclidean(&query, p), (idx, *key)))
+        .filter(|(d, _)| *d <= 0.05)
+        .collect();
+    expected.sort_by_key(|(_, (_, key))| *key);
+    expected.truncate(5);
+
+    let found: Vec<(f64, (usize, u32))> = kdtree
+        .best_n_within_by_key(&query, 0.05, 5, &squared_euclidean, |item| item.1)
+        .unwrap()
+        .into_iter()
+        .map(|(d, item)| (d, *item))
+        .collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn best_n_within_by_combines_distance_and_data() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, 10f64).unwrap();
+    kdtree.add(&POINT_B.0, 1f64).unwrap();
+    kdtree.add(&POINT_C.0, 0f64).unwrap();
+    kdtree.add(&POINT_D.0, 0f64).unwrap();
+
+    let by_score = |dist: f64, item: &f64| dist + item;
+    assert_eq!(
+        kdtree
+            .best_n_within_by(&POINT_A.0, 8.0, 2, &squared_euclidean, by_score)
+            .unwrap(),
+        vec![(2f64, &1f64), (8f64, &0f64)]
+    );
+    assert_eq!(
+        kdtree
+            .best_n_within_by(&POINT_A.0, 8.0, 0, &squared_euclidean, by_score)
+            .unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        kdtree.best_n_within_by(&[f64::NAN, 0f64], 8.0, 2, &squared_euclidean, by_score),
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
+    );
+}
+
+#[test]
+fn best_n_within_into_iter_yields_best_first() {
+    let mut kdtree: KdTree<f64, u32, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    let points: Vec<([f64; 2], u32)> = (0..500)
+        .map(|_| (rand::random::<[f64; 2]>(), rand::random::<u32>()))
+        .collect();
+    for (point, item) in points.iter() {
+        kdtree.add(point, *item).unwrap();
+    }
+
+    let query = [0.5, 0.5];
+    let mut expected: Vec<u32> = points
+        .iter()
+        .filter(|(p, _)| squared_euclidean(&query, p) <= 0.05)
+        .map(|(_, item)| *item)
+        .collect();
+    expected.sort_unstable();
+    expected.truncate(5);
+
+    let found: Vec<u32> = kdtree
+        .best_n_within_into_iter(&query, 0.05, 5, &squared_euclidean)
+        .unwrap()
+        .collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn best_n_within_into_iter_handles_empty_and_invalid_queries() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::new();
+    assert_eq!(
+        kdtree
+            .best_n_within_into_iter(&POINT_A.0, 8.0, 2, &squared_euclidean)
+            .unwrap()
+            .count(),
+        0
+    );
+
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    assert_eq!(
+        kdtree
+            .best_n_within_into_iter(&POINT_A.0, 8.0, 0, &squared_euclidean)
+            .unwrap()
+            .count(),
+        0
+    );
+    assert!(matches!(
+        kdtree.best_n_within_into_iter(&[f64::NAN, 0f64], 8.0, 2, &squared_euclidean),
+        Err(ErrorKind::NonFiniteCoordinate { dimension: 0, .. })
+    ));
+}
+
+#[test]
+fn reports_offending_dimension_and_value() {
+    let mut kdtree: KdTree<f64, usize, 3> = KdTree::new();
+
+    assert_eq!(
+        kdtree.add(&[0.0, 1.0, f64::NEG_INFINITY], 0),
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 2,
+            value: f64::NEG_INFINITY
+        })
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ErrorKind::NonFiniteCoordinate {
+                dimension: 2,
+                value: f64::NEG_INFINITY
+            }
+        ),
+        "KdTree error: non-finite coordinate -inf in dimension 2"
+    );
+}
+
+#[test]
+fn rejects_capacity_that_overflows() {
+    assert_eq!(
+        KdTree::<f64, usize, 2>::with_per_node_capacity(usize::MAX).err(),
+        Some(ErrorKind::CapacityOverflow {
+            capacity: usize::MAX
+        })
+    );
+
+    let unaddressable = isize::MAX as usize / 16 + 1;
+    assert_eq!(
+        KdTree::<f64, usize, 2>::with_per_node_capacity(unaddressable).err(),
+        Some(ErrorKind::CapacityOverflow {
+            capacity: unaddressable
+        })
+    );
+
+    // Leaves only allocate as they fill, so a large capacity that is addressable is accepted.
+    let mut kdtree =
+        KdTree::<f64, usize, 2>::with_per_node_capacity(isize::MAX as usize / 64).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    assert_eq!(kdtree.size(), 1);
+}
+
+#[test]
+fn queries_are_consistent_on_empty_trees() {
+    let kdtree: KdTree<f64, usize, 2> = KdTree::new();
+    let bad_point = [0f64, f64::NAN];
+    let bad_point_err = ErrorKind::NonFiniteCoordinate {
+        dimension: 1,
+        value: f64::NAN,
+    };
+
+    assert_eq!(
+        kdtree.nearest(&POINT_A.0, 1, &squared_euclidean),
+        Ok(vec![])
+    );
+    assert_eq!(
+        kdtree.within(&POINT_A.0, 1.0, &squared_euclidean),
+        Ok(vec![])
+    );
+    assert_eq!(
+        kdtree.best_n_within(&POINT_A.0, 1.0, 1, &squared_euclidean),
+        Ok(vec![])
+    );
+    assert_eq!(
+        kdtree.nearest_one(&POINT_A.0, &squared_euclidean),
+        Err(ErrorKind::Empty)
+    );
+
+    assert_eq!(
+        kdtree.nearest(&bad_point, 1, &squared_euclidean),
+        Err(bad_point_err)
+    );
+    assert_eq!(
+        kdtree.within(&bad_point, 1.0, &squared_euclidean),
+        Err(bad_point_err)
+    );
+    assert_eq!(
+        kdtree.within_unsorted(&bad_point, 1.0, &squared_euclidean),
+        Err(bad_point_err)
+    );
+    assert_eq!(
+        kdtree
+            .best_n_within(&bad_point, 1.0, 1, &squared_euclidean)
+            .err(),
+        Some(bad_point_err)
+    );
+    assert_eq!(
+        kdtree.nearest_one(&bad_point, &squared_euclidean).err(),
+        Some(bad_point_err)
+    );
+}
+
+#[test]
+fn best_n_within_with_zero_quantity_is_empty() {
+    let mut kdtree = KdTree::with_per_node_capacity(2).unwrap();
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+
+    assert_eq!(
+        kdtree.best_n_within(&POINT_A.0, 8.0, 0, &squared_euclidean),
+        Ok(vec![])
+    );
+}
+
+#[test]
+fn clamps_infinite_coordinates() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(1)
+        .unwrap()
+        .with_non_finite_policy(NonFinitePolicy::Clamp {
+            min: -10.0,
+            max: 10.0,
+        });
+
+    kdtree.add(&[f64::INFINITY, 0.0], 0).unwrap();
+    kdtree.add(&[f64::NEG_INFINITY, 0.0], 1).unwrap();
+    kdtree.add(&[0.0, 0.0], 2).unwrap();
+    assert_eq!(kdtree.size(), 3);
+    assert_eq!(
+        kdtree.add(&[0.0, f64::NAN], 3),
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 1,
+            value: f64::NAN
+        })
+    );
+
+    assert_eq!(
+        kdtree
+            .nearest(&[f64::INFINITY, 1.0], 2, &squared_euclidean)
+            .unwrap(),
+        vec![(1.0, &0), (101.0, &2)]
+    );
+    assert_eq!(
+        kdtree
+            .within(&[f64::NEG_INFINITY, 0.0], 1.0, &squared_euclidean)
+            .unwrap(),
+        vec![(0.0, &1)]
+    );
+    assert_eq!(kdtree.remove(&[f64::INFINITY, 0.0], &0).unwrap(), 1);
+    assert_eq!(kdtree.size(), 2);
+}
+
+#[test]
+fn skips_non_finite_query_dimensions() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2)
+        .unwrap()
+        .with_non_finite_policy(NonFinitePolicy::SkipDimension);
+    let points: Vec<[f64; 2]> = (0..100).map(|_| rand::random::<[f64; 2]>()).collect();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+    kdtree.add(&[f64::NAN, 0.5], 100).unwrap();
+    assert_eq!(kdtree.size(), 100);
+
+    let query = [f64::NAN, 0.5];
+    let mut expected: Vec<(f64, usize)> = points
+        .iter()
+        .enumerate()
+        .map(|(idx, p)| ((p[1] - 0.5) * (p[1] - 0.5), idx))
+        .collect();
+    expected.push((0.0, 100));
+    expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let nearest: Vec<(f64, usize)> = kdtree
+        .nearest(&query, 5, &squared_euclidean)
+        .unwrap()
+        .into_iter()
+        .map(|(d, item)| (d, *item))
+        .collect();
+    assert_eq!(nearest, expected[..5].to_vec());
+
+    let iterated: Vec<(f64, usize)> = kdtree
+        .iter_nearest(&query, &squared_euclidean)
+        .unwrap()
+        .take(5)
+        .map(|(d, item)| (d, *item))
+        .collect();
+    assert_eq!(iterated, expected[..5].to_vec());
+
+    assert_eq!(
+        kdtree
+            .within(&query, 0.01, &squared_euclidean)
+            .unwrap()
+            .len(),
+        expected.iter().filter(|(d, _)| *d <= 0.01).count()
+    );
+    assert!(kdtree.reverse_nearest(&query, &squared_euclidean).is_err());
+
+    assert_eq!(
+        kdtree
+            .nearest_one(&[0.25, 0.5], &squared_euclidean)
+            .unwrap(),
+        (0.0, &100)
+    );
+    assert_eq!(
+        kdtree
+            .within_shell(&[0.25, 0.5], 0.0, 1e-12, &squared_euclidean)
+            .unwrap(),
+        vec![(0.0, &100)]
+    );
+}
+
+#[test]
+fn keeps_non_finite_points_in_side_list() {
+    let mut kdtree: KdTree<f64, usize, 2> =
+        KdTree::new().with_non_finite_policy(NonFinitePolicy::SideList);
+
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&[f64::NAN, 0.0], 1).unwrap();
+    kdtree.add(&[f64::INFINITY, 0.0], 2).unwrap();
+
+    assert_eq!(kdtree.size(), 1);
+    assert_eq!(
+        kdtree.nearest(&POINT_A.0, 3, &squared_euclidean).unwrap(),
+        vec![(0.0, &0)]
+    );
+    assert_eq!(
+        kdtree.nearest(&[f64::NAN, 0.0], 1, &squared_euclidean),
+        Err(ErrorKind::NonFiniteCoordinate {
+            dimension: 0,
+            value: f64::NAN
+        })
+    );
+
+    let side: Vec<usize> = kdtree.non_finite_entries().map(|(_, item)| *item).collect();
+    assert_eq!(side, vec![1, 2]);
+
+    assert_eq!(kdtree.remove(&[f64::NAN, 0.0], &1).unwrap(), 1);
+    let side: Vec<usize> = kdtree.non_finite_entries().map(|(_, item)| *item).collect();
+    assert_eq!(side, vec![2]);
+}
+
+#[test]
+fn groups_duplicate_points() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2).unwrap();
+    for idx in 0..100 {
+        kdtree.add(&POINT_B.0, idx).unwrap();
+    }
+    assert!(kdtree.is_leaf());
+    assert_eq!(kdtree.size(), 100);
+
+    kdtree.add(&POINT_A.0, 100).unwrap();
+    kdtree.add(&POINT_C.0, 101).unwrap();
+    assert!(!kdtree.is_leaf());
+    assert_eq!(kdtree.size(), 102);
+
+    let nearest = kdtree.nearest(&[0.9, 0.9], 3, &squared_euclidean).unwrap();
+    assert_eq!(nearest.len(), 3);
+    assert!(nearest
+        .iter()
+        .all(|(d, item)| (*d - 0.02).abs() < 1e-9 && **item < 100));
+    let nearest = kdtree.nearest(&[0.1, 0.1], 2, &squared_euclidean).unwrap();
+    assert_eq!(*nearest[0].1, 100);
+    assert!((nearest[1].0 - 1.62).abs() < 1e-9 && *nearest[1].1 < 100);
+    assert_eq!(
+        kdtree
+            .nearest_one(&POINT_B.0, &squared_euclidean)
+            .unwrap()
+            .0,
+        0.0
+    );
+    assert_eq!(
+        kdtree
+            .within(&POINT_B.0, 0.5, &squared_euclidean)
+            .unwrap()
+            .len(),
+        100
+    );
+
+    let mut at_b: Vec<usize> = kdtree.get_at(&POINT_B.0).cloned().collect();
+    at_b.sort_unstable();
+    assert_eq!(at_b, (0..100).collect::<Vec<_>>());
+    assert_eq!(kdtree.get_at(&POINT_C.0).collect::<Vec<_>>(), vec![&101]);
+    assert_eq!(kdtree.get_at(&POINT_D.0).count(), 0);
+    assert_eq!(kdtree.get_at(&[f64::NAN, 0.0]).count(), 0);
+
+    let all_knn = kdtree.all_knn(2, &squared_euclidean);
+    assert_eq!(all_knn.len(), 102);
+    for (item, neighbours) in &all_knn {
+        let expected = if **item < 100 { 0.0 } else { 2.0 };
+        assert_eq!(neighbours.len(), 2);
+        assert!(neighbours.iter().all(|(_, other)| *other != *item));
+        assert!((neighbours[0].0 - expected).abs() < 1e-9);
+    }
+
+    for idx in 0..50 {
+        assert_eq!(kdtree.remove(&POINT_B.0, &idx).unwrap(), 1);
+    }
+    assert_eq!(kdtree.size(), 52);
+    assert_eq!(kdtree.get_at(&POINT_B.0).count(), 50);
+    assert!(kdtree.validate().is_ok());
+}
+
+#[test]
+fn get_at_finds_side_list_entries() {
+    let mut kdtree: KdTree<f64, usize, 2> =
+        KdTree::new().with_non_finite_policy(NonFinitePolicy::SideList);
+    kdtree.add(&[f64::NAN, 1.0], 0).unwrap();
+    kdtree.add(&[f64::NAN, 2.0], 1).unwrap();
+    kdtree.add(&[0.0, 1.0], 2).unwrap();
+
+    assert_eq!(
+        kdtree.get_at(&[f64::NAN, 1.0]).collect::<Vec<_>>(),
+        vec![&0]
+    );
+    assert_eq!(kdtree.get_at(&[0.0, 1.0]).collect::<Vec<_>>(), vec![&2]);
+}
+
+#[test]
+fn looks_up_exact_points() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(2).unwrap();
+    let points: Vec<[f64; 2]> = (0..200).map(|_| rand::random::<[f64; 2]>()).collect();
+    for (idx, point) in points.iter().enumerate() {
+        kdtree.add(point, idx).unwrap();
+    }
+
+    for (idx, point) in points.iter().enumerate() {
+        assert!(kdtree.contains(point, &idx));
+        assert!(!kdtree.contains(point, &(idx + 200)));
+        assert_eq!(kdtree.get_at(point).collect::<Vec<_>>(), vec![&idx]);
+    }
+    assert!(!kdtree.contains(&[2.0, 2.0], &0));
+    assert!(!kdtree.contains(&[f64::NAN, 2.0], &0));
+
+    for (idx, point) in points.iter().enumerate() {
+        for item in kdtree.get_at_mut(point) {
+            *item += 1000;
+        }
+        assert!(kdtree.contains(point, &(idx + 1000)));
+    }
+    assert_eq!(kdtree.get_at_mut(&[2.0, 2.0]).count(), 0);
+}
+
+#[test]
+fn reports_tree_stats() {
+    let empty: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(4).unwrap();
+    let stats = empty.stats();
+    assert_eq!((stats.stems, stats.leaves), (0, 1));
+    assert_eq!((stats.min_depth, stats.max_depth), (0, 0));
+    assert_eq!(stats.leaf_fill, vec![1]);
+
+    let mut kdtree: KdTree<f64, usize, 3> = KdTree::with_per_node_capacity(4).unwrap();
+    for idx in 0..1000 {
+        kdtree.add(&rand::random::<[f64; 3]>(), idx).unwrap();
+    }
+    for idx in 0..10 {
+        kdtree.add(&[2.0, 2.0, 2.0], idx).unwrap();
+    }
+
+    let stats = kdtree.stats();
+    assert_eq!(stats.size, 1010);
+    assert_eq!(stats.leaves, stats.stems + 1);
+    assert_eq!(stats.split_dimensions.iter().sum::<usize>(), stats.stems);
+    assert!(stats.leaf_fill.len() <= 5);
+    assert_eq!(
+        stats.leaf_fill.iter().sum::<usize>() + stats.over_capacity_leaves,
+        stats.leaves
+    );
+    assert_eq!(stats.over_capacity_leaves, 0);
+    assert!(stats.min_depth <= stats.max_depth);
+    assert!(stats.mean_depth >= stats.min_depth as f64);
+    assert!(stats.mean_depth <= stats.max_depth as f64);
+    assert!(stats.memory_bytes >= 1010 * std::mem::size_of::<([f64; 3], usize)>());
+
+    let mut unbounded: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(usize::MAX / 64)
+        .unwrap()
+        .with_non_finite_policy(NonFinitePolicy::SkipDimension);
+    unbounded.add(&[0.0, 0.0], 0).unwrap();
+    unbounded.add(&[1.0, f64::NAN], 1).unwrap();
+    let stats = unbounded.stats();
+    assert_eq!(stats.leaf_fill, vec![0, 1]);
+    assert_eq!(stats.over_capacity_leaves, 0);
+}
@@ -0,0 +1,72 @@
+extern crate kiddo;
+
+use kiddo::distance::squared_euclidean;
+use kiddo::searcher::Searcher;
+use kiddo::{ErrorKind, KdTree};
+
+fn random_tree() -> KdTree<f64, usize, 2> {
+    let mut tree = KdTree::with_per_node_capacity(4).unwrap();
+    for idx in 0..500 {
+        tree.add(&rand::random::<[f64; 2]>(), idx).unwrap();
+    }
+    tree
+}
+
+#[test]
+fn searcher_matches_allocating_queries() {
+    let tree = random_tree();
+    let mut searcher = Searcher::new();
+
+    for _ in 0..100 {
+        let query = rand::random::<[f64; 2]>();
+        let num = rand::random::<usize>() % 20;
+        let radius = rand::random::<f64>() * 0.05;
+
+        let expected = tree.nearest(&query, num, &squared_euclidean).unwrap();
+        let nearest = searcher
+            .nearest(&tree, &query, num, &squared_euclidean)
+            .unwrap();
+        assert_eq!(nearest, &expected[..]);
+
+        let expected = tree.within(&query, radius, &squared_euclidean).unwrap();
+        let within = searcher
+            .within(&tree, &query, radius, &squared_euclidean)
+            .unwrap();
+        assert_eq!(within, &expected[..]);
+    }
+}
+
+#[test]
+fn searcher_can_query_several_trees() {
+    let first = random_tree();
+    let second = random_tree();
+    let mut searcher = Searcher::with_capacity(8);
+
+    let query = [0.5, 0.5];
+    for tree in &[&first, &second] {
+        let expected = tree.nearest(&query, 8, &squared_euclidean).unwrap();
+        let nearest = searcher
+            .nearest(tree, &query, 8, &squared_euclidean)
+            .unwrap();
+        assert_eq!(nearest, &expected[..]);
+    }
+}
+
+#[test]
+fn searcher_handles_empty_trees_and_bad_points() {
+    let tree: KdTree<f64, usize, 2> = KdTree::new();
+    let mut searcher = Searcher::default();
+
+    assert!(searcher
+        .nearest(&tree, &[0.0, 0.0], 3, &squared_euclidean)
+        .unwrap()
+        .is_empty());
+    assert!(searcher
+        .within(&tree, &[0.0, 0.0], 1.0, &squared_euclidean)
+        .unwrap()
+        .is_empty());
+    assert!(matches!(
+        searcher.nearest(&tree, &[f64::NAN, 0.0], 3, &squared_euclidean),
+        Err(ErrorKind::NonFiniteCoordinate { dimension: 0, .. })
+    ));
+}
@@ -5,6 +5,8 @@ extern crate kiddo;
 
 use kiddo::distance::squared_euclidean;
 use kiddo::KdTree;
+#[cfg(feature = "serialize")]
+use kiddo::NonFinitePolicy;
 
 static POINT_A: ([f64; 2], usize) = ([0f64, 0f64], 0);
 static POINT_B: ([f64; 2], usize) = ([1f64, 1f64], 1);
@@ -36,3 +38,53 @@ fn it_serializes_and_deserializes_properly() {
         vec![]
     );
 }
+
+#[cfg(feature = "serialize")]
+#[test]
+fn it_serializes_the_non_finite_policy_at_the_root_only() {
+    let mut kdtree: KdTree<f64, usize, 2> = KdTree::with_per_node_capacity(1)
+        .unwrap()
+        .with_non_finite_policy(NonFinitePolicy::SkipDimension);
+
+    kdtree.add(&POINT_A.0, POINT_A.1).unwrap();
+    kdtree.add(&POINT_B.0, POINT_B.1).unwrap();
+    kdtree.add(&POINT_C.0, POINT_C.1).unwrap();
+
+    let serialized = serde_json::to_string(&kdtree).unwrap();
+    assert_eq!(serialized.matches("non_finite").count(), 1);
+
+    let deserialized: KdTree<f64, usize, 2> = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(
+        deserialized.non_finite_policy(),
+        NonFinitePolicy::SkipDimension
+    );
+    assert_eq!(deserialized.size(), 3);
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn it_deserializes_leaves_serialized_without_grouped_points() {
+    let serialized = r#"{"size":4,"min_bounds":[0.0,0.0],"max_bounds":[3.0,3.0],"content":{"Stem":{"left":{"size":1,"min_bounds":[0.0,0.0],"max_bounds":[0.0,0.0],"content":{"Leaf":{"points":[0.0,0.0],"bucket":[0],"capacity":2}}},"right":{"size":3,"min_bounds":[1.0,1.0],"max_bounds":[3.0,3.0],"content":{"Stem":{"left":{"size":2,"min_bounds":[1.0,1.0],"max_bounds":[1.0,1.0],"content":{"Leaf":{"points":[1.0,1.0,1.0,1.0],"bucket":[2,1],"capacity":2}}},"right":{"size":1,"min_bounds":[3.0,3.0],"max_bounds":[3.0,3.0],"content":{"Leaf":{"points":[3.0,3.0],"bucket":[3],"capacity":2}}},"split_value":2.0,"split_dimension":0}}},"split_value":0.5,"split_dimension":0}}}"#;
+
+    let mut deserialized: KdTree<f64, usize, 2> = serde_json::from_str(serialized).unwrap();
+
+    assert_eq!(deserialized.size(), 4);
+    assert!(deserialized.validate().is_ok());
+    assert_eq!(
+        deserialized.get_at(&POINT_B.0).collect::<Vec<_>>(),
+        vec![&2, &1]
+    );
+    assert_eq!(
+        deserialized
+            .nearest_one(&POINT_D.0, &squared_euclidean)
+            .unwrap(),
+        (0f64, &3)
+    );
+
+    assert_eq!(deserialized.remove(&POINT_B.0, &2).unwrap(), 1);
+    assert_eq!(
+        deserialized.get_at(&POINT_B.0).collect::<Vec<_>>(),
+        vec![&1]
+    );
+    assert!(deserialized.validate().is_ok());
+}
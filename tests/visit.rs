@@ -0,0 +1,100 @@
+extern crate kiddo;
+
+use kiddo::visit::{NodeRef, Visitor};
+use kiddo::KdTree;
+
+fn random_tree() -> (KdTree<f64, usize, 2>, Vec<[f64; 2]>) {
+    let mut tree = KdTree::with_per_node_capacity(4).unwrap();
+    let points: Vec<[f64; 2]> = (0..500).map(|_| rand::random::<[f64; 2]>()).collect();
+    for (idx, point) in points.iter().enumerate() {
+        tree.add(point, idx).unwrap();
+    }
+    (tree, points)
+}
+
+struct BoxCount<'a> {
+    min: [f64; 2],
+    max: [f64; 2],
+    found: Vec<&'a usize>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> Visitor<'a, f64, usize, 2> for BoxCount<'a> {
+    fn enter(&mut self, node: NodeRef<'a, f64, usize, 2>) -> bool {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+
+        let (min, max) = node.bounds();
+        let overlaps = (0..2).all(|d| min[d] <= self.max[d] && max[d] >= self.min[d]);
+        if let Some(items) = node.leaf_items() {
+            if overlaps {
+                for (p, item) in items {
+                    if (0..2).all(|d| p[d] >= self.min[d] && p[d] <= self.max[d]) {
+                        self.found.push(item);
+                    }
+                }
+            }
+        }
+        overlaps
+    }
+
+    fn leave(&mut self, _node: NodeRef<'a, f64, usize, 2>) {
+        self.depth -= 1;
+    }
+}
+
+#[test]
+fn visitor_can_implement_box_queries() {
+    let (tree, points) = random_tree();
+
+    let mut visitor = BoxCount {
+        min: [0.2, 0.3],
+        max: [0.5, 0.4],
+        found: Vec::new(),
+        depth: 0,
+        max_depth: 0,
+    };
+    tree.visit(&mut visitor);
+
+    let mut found: Vec<usize> = visitor.found.into_iter().cloned().collect();
+    found.sort_unstable();
+    let expected: Vec<usize> = points
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p[0] >= 0.2 && p[0] <= 0.5 && p[1] >= 0.3 && p[1] <= 0.4)
+        .map(|(idx, _)| idx)
+        .collect();
+    assert_eq!(found, expected);
+    assert_eq!(visitor.depth, 0);
+    assert!(visitor.max_depth <= tree.stats().max_depth + 1);
+}
+
+#[test]
+fn cursor_walks_every_element() {
+    let (tree, _) = random_tree();
+
+    let mut stack = vec![tree.root()];
+    let mut seen = Vec::new();
+    while let Some(node) = stack.pop() {
+        match node.split() {
+            Some((dimension, value)) => {
+                let left = node.left().unwrap();
+                let right = node.right().unwrap();
+                assert_eq!(left.size() + right.size(), node.size());
+                assert!(left.bounds().1[dimension] < value);
+                assert!(right.bounds().0[dimension] >= value);
+                assert!(node.leaf_items().is_none());
+                stack.push(right);
+                stack.push(left);
+            }
+            None => {
+                assert!(node.is_leaf() && node.left().is_none() && node.right().is_none());
+                seen.extend(node.leaf_items().unwrap().map(|(_, item)| *item));
+            }
+        }
+    }
+
+    seen.sort_unstable();
+    assert_eq!(seen, (0..500).collect::<Vec<_>>());
+}